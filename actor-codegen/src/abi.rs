@@ -0,0 +1,108 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Extraction of the methods of an actor from its `invoke` entrypoint.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
+
+use anyhow::Context;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::Item;
+
+/// The methods of an actor, as dispatched by the `match_method` macro of its `invoke` entrypoint.
+#[derive(Debug, Default, Clone)]
+pub struct ActorMethods {
+    /// Whether the actor has a `Constructor` method.
+    pub constructor: bool,
+    /// Whether the actor has a `SetUp` method.
+    pub set_up: bool,
+    /// The names of the other methods of the actor.
+    pub methods: Vec<String>,
+}
+
+/// Find the arguments of the `match_method` macro call in the input tokens.
+fn find_match_method(tokens: TokenStream) -> Option<TokenStream> {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(i) if i == "match_method" => {
+                if let Some(TokenTree::Punct(p)) = tokens.peek() {
+                    if p.as_char() == '!' {
+                        tokens.next();
+                        if let Some(TokenTree::Group(g)) = tokens.next() {
+                            return Some(g.stream());
+                        }
+                    }
+                }
+            }
+            TokenTree::Group(g) => {
+                if let Some(stream) = find_match_method(g.stream()) {
+                    return Some(stream);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parse the methods of the actor whose source file is `source`.
+pub fn parse_methods(source: &Path) -> Result<ActorMethods, anyhow::Error> {
+    let src = fs::read_to_string(source)
+        .with_context(|| format!("Could not open {}", source.display()))?;
+    let syntax =
+        syn::parse_file(&src).with_context(|| format!("Could not parse {}", source.display()))?;
+    // `invoke` is either declared as a function or generated by the `actor_utils::invoke!` macro.
+    let invoke = syntax
+        .items
+        .into_iter()
+        .find_map(|item| match item {
+            Item::Fn(f) if f.sig.ident == "invoke" => Some(f.block.into_token_stream()),
+            Item::Macro(m)
+                if m.mac
+                    .path
+                    .segments
+                    .last()
+                    .filter(|s| s.ident == "invoke")
+                    .is_some() =>
+            {
+                Some(m.mac.tokens)
+            }
+            _ => None,
+        })
+        .with_context(|| format!("Could not find invoke function on {}", source.display()))?;
+
+    let match_method = find_match_method(invoke).with_context(|| {
+        format!(
+            "Could not find match_method macro in the invoke function of {}",
+            source.display(),
+        )
+    })?;
+    let group = match_method
+        .into_iter()
+        .find_map(|t| match t {
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => Some(g),
+            _ => None,
+        })
+        .with_context(|| {
+            format!(
+                "Could not parse the match_method contents of {}",
+                source.display(),
+            )
+        })?;
+
+    let mut methods = ActorMethods::default();
+    for token in group.stream().into_iter() {
+        if let TokenTree::Literal(l) = token {
+            match l.to_string().deref() {
+                "\"Constructor\"" => methods.constructor = true,
+                "\"SetUp\"" => methods.set_up = true,
+                m => methods.methods.push(m.trim_matches('"').to_string()),
+            }
+        }
+    }
+    Ok(methods)
+}
@@ -0,0 +1,258 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Named test accounts, declared in the `[accounts]` table of the workspace `kythera.toml`:
+//!
+//! ```toml
+//! [accounts]
+//! alice = { balance = "1000000000000000000" }
+//! bob = {}
+//! ```
+//!
+//! The secp256k1 key of an account is derived from its name, so that its robust address is the
+//! same on every run. The accounts are created in the order of their names before any other actor,
+//! from [`FIRST_ACCOUNT_ID`], so that their IDs are fixed as well.
+//!
+//! Test actors generate the lookup of the accounts from their build script:
+//!
+//! ```ignore
+//! fn main() {
+//!     actor_codegen::accounts::build("../..").unwrap();
+//! }
+//! ```
+//!
+//! and include them with `include!(concat!(env!("OUT_DIR"), "/accounts.rs"))`, providing an
+//! `accounts::<name>()` function and an `accounts::<NAME>_ID` constant per account.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use convert_case::{Case, Casing};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::ActorID;
+use libsecp256k1::{PublicKey, SecretKey};
+use serde::Deserialize;
+
+/// Prefix of the hashed seed of the account keys.
+const SEED_PREFIX: &str = "kythera-account:";
+
+/// ID of the first account. The init actor assigns IDs from 100 after the singleton actors, and
+/// the account sending the messages creating the named accounts takes the first one.
+pub const FIRST_ACCOUNT_ID: ActorID = 101;
+
+/// The tables of `kythera.toml` read here.
+#[derive(Debug, Default, Deserialize)]
+struct WorkspaceAccounts {
+    #[serde(default)]
+    accounts: BTreeMap<String, AccountConfig>,
+}
+
+/// An account declared in `kythera.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct AccountConfig {
+    /// Starting balance of the account, in attoFIL.
+    #[serde(default)]
+    balance: Option<String>,
+}
+
+/// A named test account.
+#[derive(Debug, Clone)]
+pub struct NamedAccount {
+    pub name: String,
+    /// ID the account is created at.
+    pub id: ActorID,
+    /// secp256k1 secret key of the account.
+    pub secret_key: [u8; 32],
+    /// Robust address of the account.
+    pub address: Address,
+    /// Starting balance of the account.
+    pub balance: TokenAmount,
+}
+
+impl NamedAccount {
+    /// Derive the key and address of the account `name`, created at `id`.
+    pub fn new(name: &str, id: ActorID, balance: TokenAmount) -> Result<Self, anyhow::Error> {
+        let secret_key = derive_key(name);
+        let public_key = PublicKey::from_secret_key(&secret_key);
+        let address = Address::new_secp256k1(&public_key.serialize())
+            .with_context(|| format!("Could not derive address of account {name}"))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            id,
+            secret_key: secret_key.serialize(),
+            address,
+            balance,
+        })
+    }
+}
+
+/// Derive a valid secp256k1 secret key from the hash of `name`.
+fn derive_key(name: &str) -> SecretKey {
+    let mut seed = format!("{SEED_PREFIX}{name}");
+    loop {
+        let hash = blake2b_simd::Params::new()
+            .hash_length(32)
+            .hash(seed.as_bytes());
+        // Hashes over the curve order are not valid keys, hash again.
+        match SecretKey::parse_slice(hash.as_bytes()) {
+            Ok(key) => return key,
+            Err(_) => seed.push('\''),
+        }
+    }
+}
+
+/// Load the accounts declared in the `kythera.toml` of the workspace at `workspace_dir`, sorted by
+/// name and numbered in that order from [`FIRST_ACCOUNT_ID`]. Empty if it does not exist.
+pub fn load(workspace_dir: &Path) -> Result<Vec<NamedAccount>, anyhow::Error> {
+    let path = workspace_dir.join("kythera.toml");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Could not read {}", path.display()))?;
+    let workspace: WorkspaceAccounts =
+        toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?;
+
+    workspace
+        .accounts
+        .iter()
+        .zip(FIRST_ACCOUNT_ID..)
+        .map(|((name, config), id)| {
+            if !name.is_case(Case::Snake) {
+                bail!("Account name {name} must be snake case");
+            }
+            let balance = match &config.balance {
+                Some(balance) => BigInt::from_str(balance)
+                    .map(TokenAmount::from_atto)
+                    .with_context(|| format!("Invalid balance {balance} of account {name}"))?,
+                None => TokenAmount::default(),
+            };
+            NamedAccount::new(name, id, balance)
+        })
+        .collect()
+}
+
+/// Name of the constant holding the ID of the account `name`.
+fn id_constant(name: &str) -> String {
+    format!("{}_ID", name.to_case(Case::UpperSnake))
+}
+
+/// Generate the `accounts` module looking up the given accounts.
+pub fn generate(accounts: &[NamedAccount]) -> String {
+    let mut source = String::from(
+        "/// Named test accounts of the workspace `kythera.toml`.\n\
+         #[allow(dead_code)]\n\
+         pub mod accounts {\n    \
+             use actor_utils::accounts::Account;\n    \
+             use fvm_shared::address::Address;\n    \
+             use fvm_shared::ActorID;\n",
+    );
+    for account in accounts {
+        let _ = write!(
+            source,
+            "\n    /// ID of the `{name}` account.\n    \
+             pub const {constant}: ActorID = {id};\n\n    \
+             /// The `{name}` account, {address}.\n    \
+             pub fn {name}() -> Account {{\n        \
+                 Account::new(\"{name}\", {constant}, Address::from_bytes(&{bytes:?}).unwrap())\n    \
+             }}\n",
+            name = account.name,
+            constant = id_constant(&account.name),
+            id = account.id,
+            address = account.address,
+            bytes = account.address.to_bytes(),
+        );
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// Generate the accounts of the workspace at `workspace_dir` as `$OUT_DIR/accounts.rs`.
+///
+/// Meant to be called from build scripts.
+pub fn build(workspace_dir: impl AsRef<Path>) -> Result<(), anyhow::Error> {
+    let workspace_dir = workspace_dir.as_ref();
+    println!(
+        "cargo:rerun-if-changed={}",
+        workspace_dir.join("kythera.toml").display()
+    );
+
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").context("no OUT_DIR env var")?);
+    let accounts = load(workspace_dir)?;
+
+    let accounts_file = out_dir.join("accounts.rs");
+    fs::write(&accounts_file, generate(&accounts))
+        .with_context(|| format!("Could not write accounts {}", accounts_file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Workspace dir with a `kythera.toml` of the given content.
+    fn workspace(config: &str) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("kythera.toml"), config).unwrap();
+        dir
+    }
+
+    #[test]
+    fn account_key_is_derived_from_name() {
+        let alice = NamedAccount::new("alice", FIRST_ACCOUNT_ID, TokenAmount::default()).unwrap();
+
+        assert_eq!(
+            hex::encode(alice.secret_key),
+            "9410cad4d4cbd22451137bf7c7d3af6976fb53cfbf36ed74ba95be50e2e911f2"
+        );
+        assert_eq!(
+            hex::encode(alice.address.to_bytes()),
+            "016fa462d64cd9f7eace7d36373d58672134b392b0"
+        );
+        assert_eq!(derive_key("alice").serialize(), alice.secret_key);
+        assert_ne!(derive_key("bob").serialize(), alice.secret_key);
+    }
+
+    #[test]
+    fn load_accounts_sorted_with_balance() {
+        let dir = workspace("[accounts]\nbob = {}\nalice = { balance = \"1000\" }\n");
+        let accounts = load(dir.path()).unwrap();
+
+        let ids: Vec<_> = accounts.iter().map(|a| (a.name.as_str(), a.id)).collect();
+        assert_eq!(ids, [("alice", 101), ("bob", 102)]);
+        assert_eq!(accounts[0].balance, TokenAmount::from_atto(1000));
+        assert_eq!(accounts[1].balance, TokenAmount::default());
+    }
+
+    #[test]
+    fn generate_account_ids() {
+        let bob = NamedAccount::new("bob", 102, TokenAmount::default()).unwrap();
+        let source = generate(&[bob]);
+
+        assert!(source.contains("    pub const BOB_ID: ActorID = 102;\n"));
+        assert!(source.contains("        Account::new(\"bob\", BOB_ID, Address::from_bytes(&"));
+    }
+
+    #[test]
+    fn load_rejects_non_snake_case_names() {
+        let dir = workspace("[accounts]\nBobSmith = {}\n");
+        let err = load(dir.path()).unwrap_err();
+
+        assert_eq!(err.to_string(), "Account name BobSmith must be snake case");
+    }
+
+    #[test]
+    fn load_without_config_is_empty() {
+        let dir = TempDir::new().unwrap();
+        assert!(load(dir.path()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Generation of typed clients for target actors.
+//!
+//! The methods of a client are the ones found in the ABI of its target actor. Their parameter and
+//! return types are looked up in the actor `types` crate, following the naming convention:
+//! - `<Method>Params`: parameters of the method. When it is a struct with named fields, the client
+//!   method takes each field as an argument. Otherwise it takes the type as a single argument.
+//! - `<Method>Return`: value returned by the method.
+//!
+//! Types used by the fields of `<Method>Params` structs must be accessible from the root of the
+//! `types` crate, as the generated clients glob import it.
+//!
+//! Test actors generate the clients of their targets from their build script:
+//!
+//! ```ignore
+//! fn main() {
+//!     actor_codegen::client::build("../../actors/whitelist").unwrap();
+//! }
+//! ```
+//!
+//! and include them with `include!(concat!(env!("OUT_DIR"), "/whitelist.rs"))`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use convert_case::{Case, Casing};
+use quote::ToTokens;
+use syn::{Fields, Item};
+
+use crate::abi;
+use crate::package_name;
+
+/// A generated client.
+pub struct Client {
+    /// Name of the module holding the client.
+    pub module: String,
+    /// Source of the module.
+    pub source: String,
+}
+
+/// The parameters of a method, as declared in the `types` crate.
+enum Params {
+    /// No parameters declared.
+    None,
+    /// A struct with named fields, the client method takes each field as an argument.
+    Fields(Vec<(String, String)>),
+    /// Any other type, the client method takes it as a single argument.
+    Single,
+}
+
+/// Parse the top level `<Method>Params` and `<Method>Return` declarations of a `types` crate.
+fn parse_types(source: &Path) -> Result<HashMap<String, Item>, anyhow::Error> {
+    let src = fs::read_to_string(source)
+        .with_context(|| format!("Could not open {}", source.display()))?;
+    let syntax =
+        syn::parse_file(&src).with_context(|| format!("Could not parse {}", source.display()))?;
+
+    Ok(syntax
+        .items
+        .into_iter()
+        .filter_map(|item| match &item {
+            Item::Struct(s) => Some((s.ident.to_string(), item)),
+            Item::Type(t) => Some((t.ident.to_string(), item)),
+            _ => None,
+        })
+        .filter(|(name, _)| name.ends_with("Params") || name.ends_with("Return"))
+        .collect())
+}
+
+/// The files a client is generated from.
+fn sources(actor_dir: &Path) -> [PathBuf; 4] {
+    [
+        actor_dir.join("Cargo.toml"),
+        actor_dir.join("src/actor.rs"),
+        actor_dir.join("types/Cargo.toml"),
+        actor_dir.join("types/src/lib.rs"),
+    ]
+}
+
+/// Generate the client of the target actor in the input dir.
+pub fn generate(actor_dir: &Path) -> Result<Client, anyhow::Error> {
+    let [cargo_path, source, types_cargo_path, types_source] = sources(actor_dir);
+
+    let name = package_name(&cargo_path)?;
+    let methods = abi::parse_methods(&source)?.methods;
+
+    // Types shared with the test actors live in an optional `types` crate.
+    let (types_crate, types) = if types_cargo_path.exists() {
+        (
+            Some(package_name(&types_cargo_path)?.replace('-', "_")),
+            parse_types(&types_source)?,
+        )
+    } else {
+        (None, HashMap::new())
+    };
+
+    let module = name.to_case(Case::Snake);
+    let client = format!("{}Client", name.to_case(Case::Pascal));
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by actor-codegen from the ABI of the `{name}` actor, do not edit."
+    )?;
+    writeln!(out)?;
+    writeln!(out, "#[allow(dead_code, unused_imports)]")?;
+    writeln!(out, "pub mod {module} {{")?;
+    if let Some(types_crate) = &types_crate {
+        writeln!(out, "    use {types_crate}::*;")?;
+    }
+    writeln!(out, "    use actor_utils::client::{{self, ClientError}};")?;
+    writeln!(out, "    use actor_utils::runtime::Runtime;")?;
+    writeln!(out, "    use frc42_dispatch::method_hash;")?;
+    writeln!(out, "    use fvm_shared::ActorID;")?;
+    writeln!(out)?;
+    writeln!(out, "    /// Client for the `{name}` actor.")?;
+    writeln!(out, "    pub struct {client}<'a, R: Runtime> {{")?;
+    writeln!(out, "        rt: &'a R,")?;
+    writeln!(out, "        id: ActorID,")?;
+    writeln!(out, "    }}")?;
+    writeln!(out)?;
+    writeln!(out, "    impl<'a, R: Runtime> {client}<'a, R> {{")?;
+    writeln!(out, "        pub fn new(rt: &'a R, id: ActorID) -> Self {{")?;
+    writeln!(out, "            Self {{ rt, id }}")?;
+    writeln!(out, "        }}")?;
+    writeln!(out)?;
+    writeln!(out, "        pub fn id(&self) -> ActorID {{")?;
+    writeln!(out, "            self.id")?;
+    writeln!(out, "        }}")?;
+
+    for method in methods {
+        let params_name = format!("{method}Params");
+        let return_name = format!("{method}Return");
+
+        let params = match types.get(&params_name) {
+            Some(Item::Struct(s)) => match &s.fields {
+                Fields::Named(fields) => Params::Fields(
+                    fields
+                        .named
+                        .iter()
+                        .map(|f| {
+                            (
+                                f.ident.as_ref().unwrap().to_string(),
+                                f.ty.to_token_stream().to_string(),
+                            )
+                        })
+                        .collect(),
+                ),
+                _ => Params::Single,
+            },
+            Some(_) => Params::Single,
+            None => Params::None,
+        };
+
+        let (args, value) = match &params {
+            Params::None => (String::new(), String::from("None::<&()>")),
+            Params::Fields(fields) => (
+                fields
+                    .iter()
+                    .map(|(name, ty)| format!(", {name}: {ty}"))
+                    .collect(),
+                format!(
+                    "Some(&{params_name} {{ {} }})",
+                    fields
+                        .iter()
+                        .map(|(name, _)| name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ),
+            Params::Single => (
+                format!(", params: {params_name}"),
+                String::from("Some(&params)"),
+            ),
+        };
+
+        writeln!(out)?;
+        writeln!(out, "        /// Call the `{method}` method.")?;
+        if types.contains_key(&return_name) {
+            writeln!(
+                out,
+                "        pub fn {}(&self{args}) -> Result<{return_name}, ClientError> {{",
+                method.to_case(Case::Snake)
+            )?;
+            writeln!(
+                out,
+                "            client::call(self.rt, self.id, \"{method}\", method_hash!(\"{method}\"), {value})"
+            )?;
+        } else {
+            writeln!(
+                out,
+                "        pub fn {}(&self{args}) -> Result<(), ClientError> {{",
+                method.to_case(Case::Snake)
+            )?;
+            writeln!(
+                out,
+                "            client::send(self.rt, self.id, \"{method}\", method_hash!(\"{method}\"), {value})?;"
+            )?;
+            writeln!(out, "            Ok(())")?;
+        }
+        writeln!(out, "        }}")?;
+    }
+
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+
+    Ok(Client {
+        module,
+        source: out,
+    })
+}
+
+/// Generate the client of the target actor in the input dir as `$OUT_DIR/<module>.rs`.
+///
+/// Meant to be called from build scripts.
+pub fn build(actor_dir: impl AsRef<Path>) -> Result<(), anyhow::Error> {
+    let actor_dir = actor_dir.as_ref();
+    for source in sources(actor_dir) {
+        println!("cargo:rerun-if-changed={}", source.display());
+    }
+
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").context("no OUT_DIR env var")?);
+    let client = generate(actor_dir)
+        .with_context(|| format!("Could not generate client of {}", actor_dir.display()))?;
+
+    let client_file = out_dir.join(format!("{}.rs", client.module));
+    fs::write(&client_file, client.source)
+        .with_context(|| format!("Could not write client {}", client_file.display()))
+}
@@ -0,0 +1,53 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Parsing of actor sources and generation of code from them, shared by `build-helper` and the
+//! build scripts of test actors.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Context;
+use toml::Table;
+
+pub mod abi;
+pub mod accounts;
+pub mod client;
+pub mod metadata;
+
+/// Read the input Cargo.toml file.
+fn read_manifest(cargo_path: &Path) -> Result<Table, anyhow::Error> {
+    let mut cargo_file = File::open(cargo_path)
+        .with_context(|| format!("Could open {} file", cargo_path.display()))?;
+
+    // Unfortunately `toml` doesn't have from_read() like `serde_json`
+    // https://github.com/toml-rs/toml/issues/326
+    let mut cargo_str = String::new();
+    cargo_file
+        .read_to_string(&mut cargo_str)
+        .with_context(|| format!("Could not read  {} file", cargo_path.display()))?;
+
+    cargo_str
+        .parse::<Table>()
+        .with_context(|| format!("{} is not a valid TOML file", cargo_path.display()))
+}
+
+/// Read the package name of the input Cargo.toml file.
+pub fn package_name(cargo_path: &Path) -> Result<String, anyhow::Error> {
+    let cargo = read_manifest(cargo_path)?;
+
+    let name = cargo
+        .get("package")
+        .and_then(|p| p.as_table())
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .with_context(|| {
+            format!(
+                "{} is not a valid Rust Cargo.toml file, \"name\" is missing ",
+                cargo_path.display()
+            )
+        })?;
+
+    Ok(name.into())
+}
@@ -0,0 +1,208 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Kythera metadata of an actor crate, declared under `[package.metadata.kythera]` in its
+//! Cargo.toml and shipped along its artifacts.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::read_manifest;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct KytheraMetadata {
+    /// Expected failures of the `TestFail` methods, by method name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub expect: BTreeMap<String, ExpectedFailure>,
+    /// Maximum size of the built wasm module, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_budget: Option<usize>,
+    /// Variants of the target actor the test actor runs against, `default` being the actor built
+    /// without variant features. Only the default one is tested when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_variants: Vec<String>,
+    /// Parameters the target actor constructor is called with, as hex encoded DAG-CBOR.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constructor_params: Option<String>,
+    /// File holding the DAG-CBOR parameters of the target actor constructor, relative to the
+    /// crate. Read into `constructor-params`.
+    #[serde(default, skip_serializing)]
+    pub constructor_params_file: Option<PathBuf>,
+    /// Target actors deployed for the test actor instead of the one named after it, by the name
+    /// its methods look them up with.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub targets: BTreeMap<String, TargetActor>,
+    /// Variants of the actor, with the features each one is built with on top of the build
+    /// options, by variant name.
+    #[serde(default, skip_serializing)]
+    pub variants: BTreeMap<String, Vec<String>>,
+    /// Build options of the actor, overriding the ones of `kythera.toml`.
+    #[serde(default, skip_serializing)]
+    pub build: BuildOptions,
+}
+
+/// Options of the wasm build of actors, unset options falling back to the ones of a lower level.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildOptions {
+    /// Cargo profile to build with.
+    pub profile: Option<String>,
+    /// Cargo features to enable.
+    pub features: Option<Vec<String>>,
+    /// `RUSTFLAGS` of the build.
+    pub rustflags: Option<String>,
+    /// Whether to build with `--locked`.
+    pub locked: Option<bool>,
+    /// Whether to build with `--offline`.
+    pub offline: Option<bool>,
+}
+
+impl BuildOptions {
+    /// Options set in `self`, falling back to `base` for the unset ones.
+    pub fn or(&self, base: &BuildOptions) -> BuildOptions {
+        BuildOptions {
+            profile: self.profile.clone().or_else(|| base.profile.clone()),
+            features: self.features.clone().or_else(|| base.features.clone()),
+            rustflags: self.rustflags.clone().or_else(|| base.rustflags.clone()),
+            locked: self.locked.or(base.locked),
+            offline: self.offline.or(base.offline),
+        }
+    }
+}
+
+/// A target actor deployed for a test actor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TargetActor {
+    /// Artifact name of the actor, e.g. `Whitelist` or `Whitelist-strict`.
+    pub actor: String,
+    /// Parameters the actor constructor is called with, as hex encoded DAG-CBOR.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constructor_params: Option<String>,
+    /// File holding the DAG-CBOR parameters of the actor constructor, relative to the crate. Read
+    /// into `constructor-params`.
+    #[serde(default, skip_serializing)]
+    pub constructor_params_file: Option<PathBuf>,
+}
+
+impl TargetActor {
+    /// Decode the constructor parameters of the actor, if any.
+    pub fn decode_constructor_params(&self) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        decode_params(self.constructor_params.as_deref())
+    }
+}
+
+/// How a failing test method is expected to fail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ExpectedFailure {
+    /// Exit code the method must abort with.
+    pub exit_code: u32,
+    /// Regex the abort message must match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl KytheraMetadata {
+    /// Whether no metadata shipped along the artifacts is declared.
+    pub fn is_empty(&self) -> bool {
+        self.expect.is_empty()
+            && self.size_budget.is_none()
+            && self.target_variants.is_empty()
+            && self.constructor_params.is_none()
+            && self.targets.is_empty()
+    }
+
+    /// Read the Kythera metadata of the input Cargo.toml file, empty if it has none.
+    pub fn read(cargo_path: &Path) -> Result<Self, anyhow::Error> {
+        let cargo = read_manifest(cargo_path)?;
+
+        let metadata = cargo
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("kythera"));
+        let mut metadata: Self = match metadata {
+            Some(metadata) => metadata.clone().try_into().with_context(|| {
+                format!(
+                    "Invalid [package.metadata.kythera] in {}",
+                    cargo_path.display()
+                )
+            })?,
+            None => return Ok(Self::default()),
+        };
+
+        read_params_file(
+            &mut metadata.constructor_params,
+            metadata.constructor_params_file.take(),
+            cargo_path,
+        )?;
+        for target in metadata.targets.values_mut() {
+            read_params_file(
+                &mut target.constructor_params,
+                target.constructor_params_file.take(),
+                cargo_path,
+            )?;
+        }
+        Ok(metadata)
+    }
+
+    /// Decode the constructor parameters of the target actor, if any.
+    pub fn decode_constructor_params(&self) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        decode_params(self.constructor_params.as_deref())
+    }
+
+    /// Write the metadata as a TOML artifact at `path`.
+    pub fn write(&self, path: &Path) -> Result<(), anyhow::Error> {
+        let content = toml::to_string(self).context("Could not serialize Kythera metadata")?;
+        fs::write(path, content)
+            .with_context(|| format!("Could not write metadata file {}", path.display()))
+    }
+
+    /// Load the metadata artifact at `path`, empty if it does not exist.
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read metadata file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Invalid metadata file {}", path.display()))
+    }
+}
+
+/// Read the parameters `file`, relative to the crate of `cargo_path`, hex encoded into `params`.
+fn read_params_file(
+    params: &mut Option<String>,
+    file: Option<PathBuf>,
+    cargo_path: &Path,
+) -> Result<(), anyhow::Error> {
+    if let Some(file) = file {
+        if params.is_some() {
+            bail!(
+                "Both constructor-params and constructor-params-file are set in {}",
+                cargo_path.display()
+            );
+        }
+        let path = cargo_path.with_file_name(file);
+        let data = fs::read(&path).with_context(|| {
+            format!("Could not read constructor params file {}", path.display())
+        })?;
+        *params = Some(hex::encode(data));
+    }
+    Ok(())
+}
+
+/// Decode hex encoded parameters.
+fn decode_params(params: Option<&str>) -> Result<Option<Vec<u8>>, anyhow::Error> {
+    params
+        .map(|params| {
+            hex::decode(params.trim_start_matches("0x"))
+                .context("Invalid hex encoded constructor params")
+        })
+        .transpose()
+}
@@ -0,0 +1,50 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Named test accounts, declared in the workspace `kythera.toml` and created before every test.
+//!
+//! Test actors look them up through the `accounts` module generated by
+//! `actor_codegen::accounts::build`:
+//!
+//! ```ignore
+//! let alice = accounts::alice();
+//! alice.prank(rt).unwrap();
+//! whitelist.set_whitelist(accounts::bob().id_address(), true)?;
+//! ```
+
+use fvm_shared::address::Address;
+use fvm_shared::ActorID;
+
+use crate::cheatcodes::{self, CheatcodeError};
+use crate::runtime::Runtime;
+
+/// A named test account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub name: &'static str,
+    /// ID the account is created at, before any other actor is deployed.
+    pub id: ActorID,
+    /// Robust address of the account.
+    pub address: Address,
+}
+
+impl Account {
+    pub fn new(name: &'static str, id: ActorID, address: Address) -> Self {
+        Self { name, id, address }
+    }
+
+    /// ID address of the account.
+    pub fn id_address(&self) -> Address {
+        Address::new_id(self.id)
+    }
+
+    /// Impersonate the account as the sender of the next message.
+    pub fn prank(&self, rt: &impl Runtime) -> Result<(), CheatcodeError> {
+        cheatcodes::prank(rt, self.id_address())
+    }
+
+    /// Impersonate the account as the origin of the next message.
+    pub fn trick(&self, rt: &impl Runtime) -> Result<(), CheatcodeError> {
+        cheatcodes::trick(rt, self.id_address())
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Assertions for test actors.
+//!
+//! Every failed assertion aborts the actor with `USR_ASSERTION_FAILED` and a message of the form:
+//!
+//! ```text
+//! assertion failed: <assertion>
+//! <details>
+//!   at <file>:<line>:<column>
+//! ```
+//!
+//! so that Kythera reports show what failed and where, without relying on a panic hook. When unit
+//! testing natively, the abort is raised as a panic with a
+//! [`runtime::Abort`](crate::runtime::Abort) payload instead.
+
+use std::fmt::{Debug, Display};
+use std::panic::Location;
+
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::Response;
+
+use crate::runtime::Runtime;
+
+/// Abort the current actor with `USR_ASSERTION_FAILED`, reporting the failed `assertion`, its
+/// `details` and the location of the caller.
+#[track_caller]
+pub fn fail(assertion: &str, details: impl Display) -> ! {
+    let location = Location::caller();
+    let msg = format!("assertion failed: {assertion}\n{details}\n  at {location}");
+
+    #[cfg(target_arch = "wasm32")]
+    fvm_sdk::vm::abort(ExitCode::USR_ASSERTION_FAILED.value(), Some(&msg));
+
+    #[cfg(not(target_arch = "wasm32"))]
+    std::panic::panic_any(crate::runtime::Abort {
+        exit_code: ExitCode::USR_ASSERTION_FAILED,
+        msg,
+    });
+}
+
+/// Assert that `left` and `right` are equal.
+#[track_caller]
+pub fn assert_eq<T: PartialEq + Debug>(left: T, right: T) {
+    if left != right {
+        fail(
+            "assert_eq",
+            format_args!("  left: {left:?}\n right: {right:?}"),
+        );
+    }
+}
+
+/// Assert that a `send` returned with the `expected` exit code.
+#[track_caller]
+pub fn assert_exit_code(response: &Response, expected: ExitCode) {
+    if response.exit_code != expected {
+        fail(
+            "assert_exit_code",
+            format_args!(
+                "  expected: {}\n    actual: {}",
+                expected.value(),
+                response.exit_code.value()
+            ),
+        );
+    }
+}
+
+/// Assert that the state of the current actor decodes to `expected`.
+#[track_caller]
+pub fn assert_state_eq<T: DeserializeOwned + PartialEq + Debug>(rt: &impl Runtime, expected: &T) {
+    let state: T = match rt.state() {
+        Ok(state) => state,
+        Err(err) => fail(
+            "assert_state_eq",
+            format_args!("could not load state: {err}"),
+        ),
+    };
+    if &state != expected {
+        fail(
+            "assert_state_eq",
+            format_args!("     state: {state:?}\n  expected: {expected:?}"),
+        );
+    }
+}
+
+/// Assert that the call made by `f` aborted with the `expected` exit code, returning its response.
+///
+/// An abort can't be caught inside the actor that raised it, `f` should therefore wrap a `send` to
+/// the actor expected to abort.
+#[track_caller]
+pub fn expect_abort<F>(f: F, expected: ExitCode) -> Response
+where
+    F: FnOnce() -> Result<Response, ErrorNumber>,
+{
+    match f() {
+        Ok(response) if response.exit_code == expected => response,
+        Ok(response) => fail(
+            "expect_abort",
+            format_args!(
+                "  expected: {}\n    actual: {}",
+                expected.value(),
+                response.exit_code.value()
+            ),
+        ),
+        Err(err) => fail("expect_abort", format_args!("send failed: {err}")),
+    }
+}
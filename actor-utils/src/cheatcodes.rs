@@ -0,0 +1,160 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Typed bindings for the Kythera cheatcodes.
+//!
+//! Cheatcodes are methods of an actor deployed by Kythera at [`CHEATCODES_ACTOR_ID`], allowing test
+//! actors to alter the environment their tests run in.
+//!
+//! The state of a target can be replaced with [`alter_state`], but not read: actors can only get
+//! their own state root, and Kythera has no cheatcode returning the root of another actor. Tests
+//! asserting on the state of a target without getter methods are written on the host, with
+//! `TestEnv::read_state` of `build-helper` reading it from the state tree.
+
+use cid::multihash::Code;
+use cid::Cid;
+use frc42_dispatch::method_hash;
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_ipld_encoding::{CborStore, DAG_CBOR};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::Zero;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::{ActorID, MethodNum};
+use serde::ser;
+use thiserror::Error;
+
+use crate::runtime::Runtime;
+
+/// Id of the actor exposing the cheatcodes.
+pub const CHEATCODES_ACTOR_ID: ActorID = 98;
+
+/// Address of the actor exposing the cheatcodes.
+pub const CHEATCODES_ADDRESS: Address = Address::new_id(CHEATCODES_ACTOR_ID);
+
+#[derive(Error, Debug)]
+pub enum CheatcodeError {
+    #[error("could not encode {method} parameters: {source}")]
+    Encoding {
+        method: &'static str,
+        source: fvm_ipld_encoding::Error,
+    },
+    #[error("could not send {method}: {source}")]
+    Send {
+        method: &'static str,
+        source: ErrorNumber,
+    },
+    #[error("{method} exited with code {exit_code:?}")]
+    Failed {
+        method: &'static str,
+        exit_code: ExitCode,
+    },
+    #[error("could not access state of {target}: {reason}")]
+    State { target: Address, reason: String },
+}
+
+/// Call the `method` cheatcode with the given parameters, returning its return data.
+fn call<P>(
+    rt: &impl Runtime,
+    method: &'static str,
+    method_num: MethodNum,
+    params: &P,
+) -> Result<Option<IpldBlock>, CheatcodeError>
+where
+    P: ser::Serialize + ?Sized,
+{
+    let params = IpldBlock::serialize(DAG_CBOR, params)
+        .map_err(|source| CheatcodeError::Encoding { method, source })?;
+
+    let res = rt
+        .send(
+            &CHEATCODES_ADDRESS,
+            method_num,
+            Some(params),
+            TokenAmount::zero(),
+        )
+        .map_err(|source| CheatcodeError::Send { method, source })?;
+
+    if !res.exit_code.is_success() {
+        return Err(CheatcodeError::Failed {
+            method,
+            exit_code: res.exit_code,
+        });
+    }
+    Ok(res.return_data)
+}
+
+/// Set the timestamp of the following messages.
+pub fn warp(rt: &impl Runtime, timestamp: u64) -> Result<(), CheatcodeError> {
+    call(rt, "Warp", method_hash!("Warp"), &timestamp)?;
+    Ok(())
+}
+
+/// Set the epoch of the following messages.
+pub fn epoch(rt: &impl Runtime, epoch: ChainEpoch) -> Result<(), CheatcodeError> {
+    call(rt, "Epoch", method_hash!("Epoch"), &epoch)?;
+    Ok(())
+}
+
+/// Set the base fee of the following messages.
+pub fn fee(rt: &impl Runtime, fee: TokenAmount) -> Result<(), CheatcodeError> {
+    call(rt, "Fee", method_hash!("Fee"), &fee)?;
+    Ok(())
+}
+
+/// Set the chain id of the following messages.
+pub fn chain_id(rt: &impl Runtime, chain_id: u64) -> Result<(), CheatcodeError> {
+    call(rt, "ChainId", method_hash!("ChainId"), &chain_id)?;
+    Ok(())
+}
+
+/// Impersonate `caller` as the sender of the next message.
+pub fn prank(rt: &impl Runtime, caller: Address) -> Result<(), CheatcodeError> {
+    call(rt, "Prank", method_hash!("Prank"), &caller)?;
+    Ok(())
+}
+
+/// Impersonate `origin` as the origin of the next message.
+pub fn trick(rt: &impl Runtime, origin: Address) -> Result<(), CheatcodeError> {
+    call(rt, "Trick", method_hash!("Trick"), &origin)?;
+    Ok(())
+}
+
+/// Set the balance of `address` to `amount`.
+pub fn deal(
+    rt: &impl Runtime,
+    address: Address,
+    amount: TokenAmount,
+) -> Result<(), CheatcodeError> {
+    call(rt, "Deal", method_hash!("Deal"), &(address, amount))?;
+    Ok(())
+}
+
+/// Replace the root of `target` with the block at `root`, which must already be stored.
+pub fn alter_root(rt: &impl Runtime, target: Address, root: &Cid) -> Result<(), CheatcodeError> {
+    call(
+        rt,
+        "Alter",
+        method_hash!("Alter"),
+        &(target, root.to_string()),
+    )?;
+    Ok(())
+}
+
+/// Store `state` and set it as the root state of `target`, returning its `Cid`.
+pub fn alter_state<T: ser::Serialize>(
+    rt: &impl Runtime,
+    target: Address,
+    state: &T,
+) -> Result<Cid, CheatcodeError> {
+    let root = rt
+        .store()
+        .put_cbor(state, Code::Blake2b256)
+        .map_err(|err| CheatcodeError::State {
+            target,
+            reason: err.to_string(),
+        })?;
+    alter_root(rt, target, &root)?;
+    Ok(root)
+}
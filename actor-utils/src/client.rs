@@ -0,0 +1,109 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Helpers used by the clients generated by actor-codegen to call target actors.
+
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_ipld_encoding::DAG_CBOR;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::Zero;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::{ActorID, MethodNum};
+use serde::ser;
+use thiserror::Error;
+
+use crate::runtime::Runtime;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("could not encode {method} parameters: {source}")]
+    Encoding {
+        method: &'static str,
+        source: fvm_ipld_encoding::Error,
+    },
+    #[error("could not send {method}: {source}")]
+    Send {
+        method: &'static str,
+        source: ErrorNumber,
+    },
+    #[error("{method} exited with code {exit_code:?}")]
+    Failed {
+        method: &'static str,
+        exit_code: ExitCode,
+    },
+    #[error("could not decode {method} return value: {reason}")]
+    Decoding {
+        method: &'static str,
+        reason: String,
+    },
+}
+
+impl ClientError {
+    /// The exit code of the call, if it was executed but failed.
+    pub fn exit_code(&self) -> Option<ExitCode> {
+        match self {
+            ClientError::Failed { exit_code, .. } => Some(*exit_code),
+            _ => None,
+        }
+    }
+}
+
+/// Send a message calling `method` on the actor `to`, returning its return data.
+///
+/// A call exiting with a code other than `OK` is returned as a [`ClientError::Failed`].
+pub fn send<P>(
+    rt: &impl Runtime,
+    to: ActorID,
+    method: &'static str,
+    method_num: MethodNum,
+    params: Option<&P>,
+) -> Result<Option<IpldBlock>, ClientError>
+where
+    P: ser::Serialize + ?Sized,
+{
+    let params = params
+        .map(|p| IpldBlock::serialize(DAG_CBOR, p))
+        .transpose()
+        .map_err(|source| ClientError::Encoding { method, source })?;
+
+    let res = rt
+        .send(
+            &Address::new_id(to),
+            method_num,
+            params,
+            TokenAmount::zero(),
+        )
+        .map_err(|source| ClientError::Send { method, source })?;
+
+    if !res.exit_code.is_success() {
+        return Err(ClientError::Failed {
+            method,
+            exit_code: res.exit_code,
+        });
+    }
+    Ok(res.return_data)
+}
+
+/// Send a message calling `method` on the actor `to`, decoding its return value.
+pub fn call<P, R>(
+    rt: &impl Runtime,
+    to: ActorID,
+    method: &'static str,
+    method_num: MethodNum,
+    params: Option<&P>,
+) -> Result<R, ClientError>
+where
+    P: ser::Serialize + ?Sized,
+    R: DeserializeOwned,
+{
+    let ret = send(rt, to, method, method_num, params)?.ok_or_else(|| ClientError::Decoding {
+        method,
+        reason: String::from("no value returned"),
+    })?;
+    ret.deserialize().map_err(|err| ClientError::Decoding {
+        method,
+        reason: err.to_string(),
+    })
+}
@@ -0,0 +1,61 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Actor entrypoint generation.
+
+/// Install a panic hook exiting the actor with `USR_ASSERTION_FAILED` and the panic message and
+/// location.
+///
+/// Actors are built with `panic = "abort"`, without this hook a panic ends up as an opaque trap.
+#[cfg(target_arch = "wasm32")]
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        fvm_sdk::vm::exit(
+            fvm_shared::error::ExitCode::USR_ASSERTION_FAILED.value(),
+            None,
+            Some(&format!("{info}")),
+        )
+    }));
+}
+
+/// Generate the `invoke` entrypoint of an actor, installing the panic hook before running `body`.
+///
+/// When given two arguments, the first one is bound to a [`FvmRuntime`](crate::runtime::FvmRuntime).
+/// The entrypoint is only generated when building for wasm, so that the rest of the actor can be
+/// unit tested natively.
+///
+/// ```ignore
+/// actor_utils::invoke!(|rt, input| {
+///     match_method!(rt.method_number(), {
+///         "Constructor" => {
+///             dispatch(Constructor(&rt));
+///             fvm_sdk::NO_DATA_BLOCK_ID
+///         },
+///         _ => {
+///             rt.abort(ExitCode::USR_UNHANDLED_MESSAGE, "Unknown method number");
+///         }
+///     })
+/// });
+/// ```
+#[macro_export]
+macro_rules! invoke {
+    (|$input:ident| $body:block) => {
+        #[cfg(target_arch = "wasm32")]
+        #[no_mangle]
+        fn invoke($input: u32) -> u32 {
+            $crate::entrypoint::install_panic_hook();
+            $body
+        }
+    };
+    (|$rt:ident, $input:ident| $body:block) => {
+        #[cfg(target_arch = "wasm32")]
+        #[no_mangle]
+        fn invoke($input: u32) -> u32 {
+            $crate::entrypoint::install_panic_hook();
+            #[allow(unused_imports)]
+            use $crate::runtime::Runtime;
+            let $rt = $crate::runtime::FvmRuntime::default();
+            $body
+        }
+    };
+}
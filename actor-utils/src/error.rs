@@ -0,0 +1,93 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Errors returned by actor methods, carrying the exit code the actor should exit with.
+
+use fvm_shared::error::ExitCode;
+use thiserror::Error;
+
+/// The error of an actor method, made of the exit code to exit with and a message.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("actor error ({}): {msg}", exit_code.value())]
+pub struct ActorError {
+    exit_code: ExitCode,
+    msg: String,
+}
+
+/// The result of an actor method.
+pub type ActorResult<T> = Result<T, ActorError>;
+
+impl ActorError {
+    /// Create a new error with any exit code.
+    pub fn unchecked(exit_code: ExitCode, msg: impl Into<String>) -> Self {
+        Self {
+            exit_code,
+            msg: msg.into(),
+        }
+    }
+
+    pub fn illegal_argument(msg: impl Into<String>) -> Self {
+        Self::unchecked(ExitCode::USR_ILLEGAL_ARGUMENT, msg)
+    }
+
+    pub fn not_found(msg: impl Into<String>) -> Self {
+        Self::unchecked(ExitCode::USR_NOT_FOUND, msg)
+    }
+
+    pub fn forbidden(msg: impl Into<String>) -> Self {
+        Self::unchecked(ExitCode::USR_FORBIDDEN, msg)
+    }
+
+    pub fn insufficient_funds(msg: impl Into<String>) -> Self {
+        Self::unchecked(ExitCode::USR_INSUFFICIENT_FUNDS, msg)
+    }
+
+    pub fn illegal_state(msg: impl Into<String>) -> Self {
+        Self::unchecked(ExitCode::USR_ILLEGAL_STATE, msg)
+    }
+
+    pub fn serialization(msg: impl Into<String>) -> Self {
+        Self::unchecked(ExitCode::USR_SERIALIZATION, msg)
+    }
+
+    pub fn unhandled_message(msg: impl Into<String>) -> Self {
+        Self::unchecked(ExitCode::USR_UNHANDLED_MESSAGE, msg)
+    }
+
+    pub fn unspecified(msg: impl Into<String>) -> Self {
+        Self::unchecked(ExitCode::USR_UNSPECIFIED, msg)
+    }
+
+    pub fn assertion_failed(msg: impl Into<String>) -> Self {
+        Self::unchecked(ExitCode::USR_ASSERTION_FAILED, msg)
+    }
+
+    /// The exit code of the error.
+    pub fn exit_code(&self) -> ExitCode {
+        self.exit_code
+    }
+
+    /// The message of the error.
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+}
+
+impl From<fvm_ipld_encoding::Error> for ActorError {
+    fn from(err: fvm_ipld_encoding::Error) -> Self {
+        Self::serialization(err.to_string())
+    }
+}
+
+/// Unwrap the result of an actor method, exiting with the exit code and message of the error if
+/// it failed.
+#[cfg(target_arch = "wasm32")]
+pub fn dispatch<T, E: Into<ActorError>>(result: Result<T, E>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => {
+            let err = err.into();
+            fvm_sdk::vm::exit(err.exit_code.value(), None, Some(&err.msg))
+        }
+    }
+}
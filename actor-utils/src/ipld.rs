@@ -0,0 +1,72 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! IPLD helpers to store and load DAG-CBOR blocks.
+
+use cid::{multihash::Code, Cid};
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::{RawBytes, DAG_CBOR};
+use fvm_shared::error::ErrorNumber;
+use serde::ser;
+use thiserror::Error;
+
+use crate::error::ActorError;
+
+#[derive(Error, Debug)]
+pub enum IpldError {
+    #[error("ipld encoding error: {0}")]
+    Encoding(#[from] fvm_ipld_encoding::Error),
+    #[error("ipld blockstore error: {0}")]
+    Blockstore(#[from] ErrorNumber),
+}
+
+impl From<IpldError> for ActorError {
+    fn from(err: IpldError) -> Self {
+        match err {
+            IpldError::Encoding(err) => err.into(),
+            IpldError::Blockstore(err) => ActorError::illegal_state(err.to_string()),
+        }
+    }
+}
+
+/// Encode `value` as DAG-CBOR and store it, returning the `Cid` of the block.
+pub fn put_cbor<T>(value: &T) -> Result<Cid, IpldError>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let data = fvm_ipld_encoding::to_vec(value)?;
+    Ok(fvm_sdk::ipld::put(
+        Code::Blake2b256.into(),
+        32,
+        DAG_CBOR,
+        &data,
+    )?)
+}
+
+/// Load the block at `cid` and decode it from DAG-CBOR.
+pub fn get_cbor<T: DeserializeOwned>(cid: &Cid) -> Result<T, IpldError> {
+    let data = fvm_sdk::ipld::get(cid)?;
+    Ok(fvm_ipld_encoding::from_slice(&data)?)
+}
+
+/// Deserialize message parameters into given struct.
+pub fn deserialize_params<D: DeserializeOwned>(params: u32) -> D {
+    let params = fvm_sdk::message::params_raw(params)
+        .expect("Could not get message parameters")
+        .expect("Expected message parameters but got none");
+
+    let params = RawBytes::new(params.data);
+
+    params
+        .deserialize()
+        .expect("Should be able to deserialize message params into arguments of called method")
+}
+
+/// Store `value` as the return value of the current method, returning the id of its block.
+pub fn return_ipld<T>(value: &T) -> Result<u32, IpldError>
+where
+    T: ser::Serialize + ?Sized,
+{
+    let bytes = fvm_ipld_encoding::to_vec(value)?;
+    Ok(fvm_sdk::ipld::put_block(DAG_CBOR, bytes.as_slice())?)
+}
@@ -0,0 +1,15 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Utilities shared by the Kythera target and test actors of the workspace.
+
+pub mod accounts;
+pub mod assert;
+pub mod cheatcodes;
+pub mod client;
+pub mod entrypoint;
+pub mod error;
+#[cfg(target_arch = "wasm32")]
+pub mod ipld;
+pub mod runtime;
+pub mod targets;
@@ -0,0 +1,103 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use anyhow::anyhow;
+use cid::multihash::Code;
+use cid::Cid;
+use fvm_ipld_blockstore::{Block, Blockstore};
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::sys::SendFlags;
+use fvm_shared::{ActorID, MethodNum, Response};
+
+use super::Runtime;
+use crate::error::{ActorError, ActorResult};
+
+/// [`Runtime`] backed by the FVM syscalls.
+#[derive(Default, Clone, Copy)]
+pub struct FvmRuntime {
+    blockstore: FvmBlockstore,
+}
+
+impl Runtime for FvmRuntime {
+    type Blockstore = FvmBlockstore;
+
+    fn caller(&self) -> ActorID {
+        fvm_sdk::message::caller()
+    }
+
+    fn receiver(&self) -> ActorID {
+        fvm_sdk::message::receiver()
+    }
+
+    fn method_number(&self) -> MethodNum {
+        fvm_sdk::message::method_number()
+    }
+
+    fn value_received(&self) -> TokenAmount {
+        fvm_sdk::message::value_received()
+    }
+
+    fn root(&self) -> ActorResult<Cid> {
+        fvm_sdk::sself::root()
+            .map_err(|err| ActorError::illegal_state(format!("could not get root: {err}")))
+    }
+
+    fn set_root(&self, root: &Cid) -> ActorResult<()> {
+        fvm_sdk::sself::set_root(root)
+            .map_err(|err| ActorError::illegal_state(format!("could not set root: {err}")))
+    }
+
+    fn store(&self) -> &FvmBlockstore {
+        &self.blockstore
+    }
+
+    fn send(
+        &self,
+        to: &Address,
+        method: MethodNum,
+        params: Option<IpldBlock>,
+        value: TokenAmount,
+    ) -> Result<Response, ErrorNumber> {
+        fvm_sdk::send::send(to, method, params, value, None, SendFlags::empty())
+    }
+
+    fn resolve_address(&self, address: &Address) -> Option<ActorID> {
+        fvm_sdk::actor::resolve_address(address)
+    }
+
+    fn abort(&self, exit_code: ExitCode, msg: &str) -> ! {
+        fvm_sdk::vm::abort(exit_code.value(), Some(msg))
+    }
+}
+
+/// [`Blockstore`] backed by the FVM ipld syscalls.
+#[derive(Default, Clone, Copy)]
+pub struct FvmBlockstore;
+
+impl Blockstore for FvmBlockstore {
+    fn get(&self, k: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+        fvm_sdk::ipld::get(k)
+            .map(Some)
+            .map_err(|err| anyhow!("could not get block {k}: {err}"))
+    }
+
+    fn put_keyed(&self, k: &Cid, block: &[u8]) -> anyhow::Result<()> {
+        let code = Code::try_from(k.hash().code()).map_err(|err| anyhow!(err.to_string()))?;
+        let cid = self.put(code, &Block::new(k.codec(), block))?;
+        if k != &cid {
+            return Err(anyhow!("block {k} was stored with cid {cid}"));
+        }
+        Ok(())
+    }
+
+    fn put<D>(&self, mh_code: Code, block: &Block<D>) -> anyhow::Result<Cid>
+    where
+        D: AsRef<[u8]>,
+    {
+        fvm_sdk::ipld::put(mh_code.into(), 32, block.codec, block.data.as_ref())
+            .map_err(|err| anyhow!("could not put block: {err}"))
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+use cid::Cid;
+use fvm_ipld_blockstore::MemoryBlockstore;
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::Zero;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::{ActorID, MethodNum, Response};
+
+use super::Runtime;
+use crate::error::{ActorError, ActorResult};
+
+/// The payload of the panic raised by [`MockRuntime::abort`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Abort {
+    pub exit_code: ExitCode,
+    pub msg: String,
+}
+
+/// A send the actor is expected to make, and the response it gets back.
+#[derive(Debug)]
+struct ExpectedSend {
+    to: Address,
+    method: MethodNum,
+    params: Option<IpldBlock>,
+    response: Response,
+}
+
+/// In memory [`Runtime`] to unit test actors natively.
+///
+/// Aborts are raised as panics with an [`Abort`] payload, see [`catch_abort`]. Sends to other
+/// actors must be stubbed beforehand with [`MockRuntime::expect_send`].
+pub struct MockRuntime {
+    pub caller: ActorID,
+    pub receiver: ActorID,
+    pub method_number: MethodNum,
+    pub value_received: TokenAmount,
+    /// IDs of the actors with robust addresses, resolved by `resolve_address`.
+    pub addresses: HashMap<Address, ActorID>,
+    root: RefCell<Option<Cid>>,
+    store: MemoryBlockstore,
+    expected_sends: RefCell<VecDeque<ExpectedSend>>,
+}
+
+/// Handle on a send expectation, registered once its response is set with [`ExpectSend::returns`].
+#[must_use = "the expectation is only registered by calling `returns`"]
+pub struct ExpectSend<'a> {
+    rt: &'a MockRuntime,
+    to: Address,
+    method: MethodNum,
+    params: Option<IpldBlock>,
+}
+
+impl ExpectSend<'_> {
+    /// Respond to the expected send with `exit_code` and `return_data`.
+    pub fn returns(self, exit_code: ExitCode, return_data: Option<IpldBlock>) {
+        self.rt.expected_sends.borrow_mut().push_back(ExpectedSend {
+            to: self.to,
+            method: self.method,
+            params: self.params,
+            response: Response {
+                exit_code,
+                return_data,
+            },
+        });
+    }
+}
+
+impl MockRuntime {
+    /// Create a new runtime for the actor `receiver`, without any state.
+    pub fn new(receiver: ActorID) -> Self {
+        Self {
+            caller: 0,
+            receiver,
+            method_number: 0,
+            value_received: TokenAmount::zero(),
+            addresses: HashMap::new(),
+            root: RefCell::new(None),
+            store: MemoryBlockstore::new(),
+            expected_sends: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Expect the actor to send `method` to `to` with `params`.
+    ///
+    /// Expectations must be met in the order they are registered: any other send panics.
+    pub fn expect_send(
+        &self,
+        to: Address,
+        method: MethodNum,
+        params: Option<IpldBlock>,
+    ) -> ExpectSend<'_> {
+        ExpectSend {
+            rt: self,
+            to,
+            method,
+            params,
+        }
+    }
+
+    /// Panic if some expected sends were not made.
+    ///
+    /// Also checked when the runtime is dropped.
+    pub fn verify(&self) {
+        let expected_sends = self.expected_sends.borrow();
+        if !expected_sends.is_empty() {
+            panic!("expected sends were not made: {:?}", expected_sends);
+        }
+    }
+}
+
+impl Drop for MockRuntime {
+    fn drop(&mut self) {
+        // Do not panic while unwinding, it would abort and hide the original failure.
+        if !thread::panicking() {
+            self.verify();
+        }
+    }
+}
+
+impl Runtime for MockRuntime {
+    type Blockstore = MemoryBlockstore;
+
+    fn caller(&self) -> ActorID {
+        self.caller
+    }
+
+    fn receiver(&self) -> ActorID {
+        self.receiver
+    }
+
+    fn method_number(&self) -> MethodNum {
+        self.method_number
+    }
+
+    fn value_received(&self) -> TokenAmount {
+        self.value_received.clone()
+    }
+
+    fn root(&self) -> ActorResult<Cid> {
+        self.root
+            .borrow()
+            .ok_or_else(|| ActorError::illegal_state("actor has no state"))
+    }
+
+    fn set_root(&self, root: &Cid) -> ActorResult<()> {
+        *self.root.borrow_mut() = Some(*root);
+        Ok(())
+    }
+
+    fn store(&self) -> &MemoryBlockstore {
+        &self.store
+    }
+
+    fn send(
+        &self,
+        to: &Address,
+        method: MethodNum,
+        params: Option<IpldBlock>,
+        _value: TokenAmount,
+    ) -> Result<Response, ErrorNumber> {
+        let expected = match self.expected_sends.borrow_mut().pop_front() {
+            Some(expected) => expected,
+            None => panic!("unexpected send of method {method} to {to}"),
+        };
+        if expected.to != *to || expected.method != method || expected.params != params {
+            panic!(
+                "unexpected send of method {method} to {to} with params {params:?}, \
+                 expected method {} to {} with params {:?}",
+                expected.method, expected.to, expected.params
+            );
+        }
+        Ok(expected.response)
+    }
+
+    fn resolve_address(&self, address: &Address) -> Option<ActorID> {
+        address
+            .id()
+            .ok()
+            .or_else(|| self.addresses.get(address).copied())
+    }
+
+    fn abort(&self, exit_code: ExitCode, msg: &str) -> ! {
+        panic::panic_any(Abort {
+            exit_code,
+            msg: msg.to_string(),
+        })
+    }
+}
+
+/// Run `f`, catching its abort with `exit_code`, and return the abort.
+///
+/// Panics if `f` returns or panics for another reason.
+pub fn catch_abort<R>(f: impl FnOnce() -> R, exit_code: ExitCode) -> Abort {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(_) => panic!("expected abort with exit code {exit_code:?}, but returned"),
+        Err(payload) => match payload.downcast::<Abort>() {
+            Ok(abort) if abort.exit_code == exit_code => *abort,
+            Ok(abort) => panic!("expected abort with exit code {exit_code:?}, got {abort:?}"),
+            Err(payload) => panic::resume_unwind(payload),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: Address = Address::new_id(100);
+
+    fn params(value: u64) -> Option<IpldBlock> {
+        IpldBlock::serialize_cbor(&value).unwrap()
+    }
+
+    fn send(rt: &MockRuntime, to: Address, method: MethodNum, value: u64) -> Response {
+        rt.send(&to, method, params(value), TokenAmount::zero())
+            .unwrap()
+    }
+
+    #[test]
+    fn sends_matched_in_order() {
+        let rt = MockRuntime::new(101);
+        rt.expect_send(TARGET, 1, params(1))
+            .returns(ExitCode::OK, None);
+        rt.expect_send(TARGET, 2, params(2))
+            .returns(ExitCode::OK, None);
+
+        send(&rt, TARGET, 1, 1);
+        send(&rt, TARGET, 2, 2);
+        rt.verify();
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected send")]
+    fn out_of_order_send_panics() {
+        let rt = MockRuntime::new(101);
+        rt.expect_send(TARGET, 1, params(1))
+            .returns(ExitCode::OK, None);
+        rt.expect_send(TARGET, 2, params(2))
+            .returns(ExitCode::OK, None);
+
+        send(&rt, TARGET, 2, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected send")]
+    fn wrong_recipient_panics() {
+        let rt = MockRuntime::new(101);
+        rt.expect_send(TARGET, 1, params(1))
+            .returns(ExitCode::OK, None);
+
+        send(&rt, Address::new_id(102), 1, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected send")]
+    fn wrong_method_panics() {
+        let rt = MockRuntime::new(101);
+        rt.expect_send(TARGET, 1, params(1))
+            .returns(ExitCode::OK, None);
+
+        send(&rt, TARGET, 2, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected send")]
+    fn wrong_params_panics() {
+        let rt = MockRuntime::new(101);
+        rt.expect_send(TARGET, 1, params(1))
+            .returns(ExitCode::OK, None);
+
+        send(&rt, TARGET, 1, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected send")]
+    fn send_without_expectation_panics() {
+        let rt = MockRuntime::new(101);
+
+        send(&rt, TARGET, 1, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected sends were not made")]
+    fn verify_fails_on_unmet_expectation() {
+        let rt = MockRuntime::new(101);
+        rt.expect_send(TARGET, 1, params(1))
+            .returns(ExitCode::OK, None);
+
+        rt.verify();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected sends were not made")]
+    fn drop_fails_on_unmet_expectation() {
+        let rt = MockRuntime::new(101);
+        rt.expect_send(TARGET, 1, params(1))
+            .returns(ExitCode::OK, None);
+
+        drop(rt);
+    }
+
+    #[test]
+    fn send_returns_response() {
+        let rt = MockRuntime::new(101);
+        rt.expect_send(TARGET, 1, params(1))
+            .returns(ExitCode::USR_FORBIDDEN, params(42));
+
+        let response = send(&rt, TARGET, 1, 1);
+        assert_eq!(response.exit_code, ExitCode::USR_FORBIDDEN);
+        assert_eq!(response.return_data, params(42));
+        assert_eq!(
+            response.return_data.unwrap().deserialize::<u64>().unwrap(),
+            42
+        );
+    }
+
+    #[test]
+    fn catch_abort_returns_abort() {
+        let rt = MockRuntime::new(101);
+
+        let abort = catch_abort(
+            || rt.abort(ExitCode::USR_FORBIDDEN, "forbidden"),
+            ExitCode::USR_FORBIDDEN,
+        );
+        assert_eq!(abort.msg, "forbidden");
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Abstraction over the environment an actor runs in.
+//!
+//! Actor methods written against [`Runtime`] run on the FVM through [`FvmRuntime`], and can be unit
+//! tested natively with `cargo test` through [`MockRuntime`].
+
+use cid::multihash::Code;
+use cid::Cid;
+use fvm_ipld_blockstore::Blockstore;
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_ipld_encoding::CborStore;
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::{ErrorNumber, ExitCode};
+use fvm_shared::{ActorID, MethodNum, Response};
+use serde::ser;
+
+use crate::error::{ActorError, ActorResult};
+
+#[cfg(target_arch = "wasm32")]
+mod fvm;
+#[cfg(not(target_arch = "wasm32"))]
+mod mock;
+
+#[cfg(target_arch = "wasm32")]
+pub use fvm::{FvmBlockstore, FvmRuntime};
+#[cfg(not(target_arch = "wasm32"))]
+pub use mock::{catch_abort, Abort, ExpectSend, MockRuntime};
+
+/// The environment of an actor: message context, state, blockstore and calls to other actors.
+pub trait Runtime {
+    type Blockstore: Blockstore;
+
+    /// The id of the actor that sent the current message.
+    fn caller(&self) -> ActorID;
+
+    /// The id of the actor receiving the current message.
+    fn receiver(&self) -> ActorID;
+
+    /// The method number of the current message.
+    fn method_number(&self) -> MethodNum;
+
+    /// The value received with the current message.
+    fn value_received(&self) -> TokenAmount;
+
+    /// The root of the actor state.
+    fn root(&self) -> ActorResult<Cid>;
+
+    /// Set the root of the actor state.
+    fn set_root(&self, root: &Cid) -> ActorResult<()>;
+
+    /// The blockstore of the actor.
+    fn store(&self) -> &Self::Blockstore;
+
+    /// Send a message to another actor.
+    fn send(
+        &self,
+        to: &Address,
+        method: MethodNum,
+        params: Option<IpldBlock>,
+        value: TokenAmount,
+    ) -> Result<Response, ErrorNumber>;
+
+    /// Resolve `address` to the ID of its actor, if there is one.
+    fn resolve_address(&self, address: &Address) -> Option<ActorID>;
+
+    /// Abort the execution with the input exit code and message.
+    fn abort(&self, exit_code: ExitCode, msg: &str) -> !;
+
+    /// Load and decode the actor state.
+    fn state<T: DeserializeOwned>(&self) -> ActorResult<T> {
+        let root = self.root()?;
+        self.store()
+            .get_cbor(&root)
+            .map_err(|err| ActorError::serialization(err.to_string()))?
+            .ok_or_else(|| ActorError::illegal_state(format!("state {root} not found")))
+    }
+
+    /// Store `state` and set it as the actor state.
+    fn set_state<T: ser::Serialize>(&self, state: &T) -> ActorResult<()> {
+        let root = self
+            .store()
+            .put_cbor(state, Code::Blake2b256)
+            .map_err(|err| ActorError::serialization(err.to_string()))?;
+        self.set_root(&root)
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Target actors deployed for a test actor, passed to its methods by name.
+//!
+//! Test actors declaring their targets in `[package.metadata.kythera.targets]` get a [`Targets`]
+//! map as the parameter of their methods, instead of the ID of a single target:
+//!
+//! ```ignore
+//! pub fn TestGatedTransfer(rt: &impl Runtime, targets: Targets) {
+//!     let token = TokenClient::new(rt, targets.get("token"));
+//!     let whitelist = WhitelistClient::new(rt, targets.get("whitelist"));
+//!     ..
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use fvm_shared::address::Address;
+use fvm_shared::ActorID;
+use serde::{Deserialize, Serialize};
+
+use crate::assert;
+
+/// IDs of the deployed target actors, by name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Targets(BTreeMap<String, ActorID>);
+
+impl Targets {
+    pub fn new(targets: BTreeMap<String, ActorID>) -> Self {
+        Self(targets)
+    }
+
+    /// ID of the target `name`, failing as an assertion if there is none.
+    #[track_caller]
+    pub fn get(&self, name: &str) -> ActorID {
+        match self.0.get(name) {
+            Some(id) => *id,
+            None => assert::fail(
+                "target exists",
+                format_args!("  no target {name} in {:?}", self.0.keys()),
+            ),
+        }
+    }
+
+    /// ID address of the target `name`, failing as an assertion if there is none.
+    #[track_caller]
+    pub fn address(&self, name: &str) -> Address {
+        Address::new_id(self.get(name))
+    }
+
+    /// ID of the target `name`, if there is one.
+    pub fn find(&self, name: &str) -> Option<ActorID> {
+        self.0.get(name).copied()
+    }
+
+    /// Names and IDs of the targets.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, ActorID)> {
+        self.0.iter().map(|(name, id)| (name.as_str(), *id))
+    }
+}
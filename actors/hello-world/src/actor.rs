@@ -59,8 +59,7 @@ where
     let bytes = fvm_ipld_encoding::to_vec(value)?;
     Ok(fvm_sdk::ipld::put_block(DAG_CBOR, bytes.as_slice())?)
 }
-#[no_mangle]
-fn invoke(_input: u32) -> u32 {
+actor_utils::invoke!(|_input| {
     let method_num = fvm_sdk::message::method_number();
     match_method!(
         method_num,
@@ -80,7 +79,7 @@ fn invoke(_input: u32) -> u32 {
             }
         }
     )
-}
+});
 
 // `Constructor` for the target actor we are using in our tests.
 #[allow(non_snake_case)]
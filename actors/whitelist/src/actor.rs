@@ -1,93 +1,168 @@
 use std::collections::HashMap;
+use actor_utils::error::{ActorError, ActorResult};
+use actor_utils::runtime::Runtime;
 use fvm_shared::address::Address;
-use frc42_dispatch::match_method;
-use fvm_sdk::NO_DATA_BLOCK_ID;
-use fvm_shared::error::ExitCode;
+use whitelist_types::{
+    ActorState, ConstructorParams, IsWhitelistedParams, IsWhitelistedReturn,
+    SetAdministratorParams, SetWhitelistParams,
+};
+
+actor_utils::invoke!(|rt, input| {
+    use actor_utils::error::dispatch;
+    use actor_utils::ipld::{deserialize_params, return_ipld};
+    use fvm_sdk::NO_DATA_BLOCK_ID;
+    use frc42_dispatch::match_method;
+    use fvm_shared::error::ExitCode;
 
-use crate::utils;
-
-#[no_mangle]
-fn invoke(input: u32) -> u32 {
-    let method_num = fvm_sdk::message::method_number();
     match_method!(
-        method_num,
+        rt.method_number(),
         {
             "Constructor" => {
-                Constructor();
+                dispatch(Constructor(&rt, deserialize_params(input)));
                 NO_DATA_BLOCK_ID
             },
             "SetAdministrator" => {
-                SetAdministrator(input);
+                dispatch(SetAdministrator(&rt, deserialize_params(input)));
                 NO_DATA_BLOCK_ID
             },
             "SetWhitelist" => {
-                SetWhitelist(input);
+                dispatch(SetWhitelist(&rt, deserialize_params(input)));
                 NO_DATA_BLOCK_ID
             },
             "IsWhitelisted" => {
-                IsWhitelisted(input)
+                let is_whitelisted = dispatch(IsWhitelisted(&rt, deserialize_params(input)));
+                dispatch(return_ipld(&is_whitelisted))
             },
             _ => {
-                fvm_sdk::vm::abort(
-                    ExitCode::USR_UNHANDLED_MESSAGE.value(),
-                    Some("Unknown method number"),
-                );
+                rt.abort(ExitCode::USR_UNHANDLED_MESSAGE, "Unknown method number");
             }
         }
     )
-}
+});
 
 #[allow(non_snake_case)]
-fn Constructor() {
-    let initial_state = utils::ActorState {
-        admin: Address::new_id(0),
+pub fn Constructor(rt: &impl Runtime, params: ConstructorParams) -> ActorResult<()> {
+    let initial_state = ActorState {
+        admin: params.admin,
         whitelist: HashMap::new()
     };
 
-    let cid = initial_state.save();
-    fvm_sdk::sself::set_root(&cid).unwrap();
+    rt.set_state(&initial_state)
 }
 
 #[allow(non_snake_case)]
-fn SetAdministrator(input: u32) {
-    let new_administrator: Address = utils::deserialize_params(input);
-
-    let mut current_state: utils::ActorState = utils::ActorState::load(&fvm_sdk::sself::root().unwrap());
-
-    assert_eq!(current_state.admin.id().unwrap(), 0, "Adminstrator is already set");
+pub fn SetAdministrator(
+    rt: &impl Runtime,
+    new_administrator: SetAdministratorParams,
+) -> ActorResult<()> {
+    let mut current_state: ActorState = rt.state()?;
+
+    if current_state.admin != Address::new_id(0) {
+        return Err(ActorError::forbidden("Administrator is already set"));
+    }
 
     current_state.admin = new_administrator;
 
-    let cid = current_state.save();
-    fvm_sdk::sself::set_root(&cid).unwrap();
+    rt.set_state(&current_state)
 }
 
 #[allow(non_snake_case)]
-fn IsWhitelisted(input: u32) -> u32 {
-    let address: Address = utils::deserialize_params(input);
+pub fn IsWhitelisted(
+    rt: &impl Runtime,
+    address: IsWhitelistedParams,
+) -> ActorResult<IsWhitelistedReturn> {
+    let current_state: ActorState = rt.state()?;
 
-    let current_state: utils::ActorState = utils::ActorState::load(&fvm_sdk::sself::root().unwrap());
+    Ok(*current_state.whitelist.get(&address).unwrap_or(&false))
+}
 
-    return match current_state.whitelist.get(&address) {
-        Some(boolean) => {
-            utils::return_ipld(boolean).unwrap()
-        }
-        _ => utils::return_ipld(&false).unwrap()
+#[allow(non_snake_case)]
+pub fn SetWhitelist(rt: &impl Runtime, params: SetWhitelistParams) -> ActorResult<()> {
+    let mut current_state: ActorState = rt.state()?;
+
+    if Address::new_id(rt.caller()) != current_state.admin {
+        return Err(ActorError::forbidden("Only administrator can set whitelist"));
     }
+
+    current_state.whitelist.insert(params.address, params.whitelisted);
+
+    rt.set_state(&current_state)
 }
 
-#[allow(non_snake_case)]
-fn SetWhitelist(input: u32) {
-    let mut current_state: utils::ActorState = utils::ActorState::load(&fvm_sdk::sself::root().unwrap());
+#[cfg(test)]
+mod tests {
+    use actor_utils::runtime::MockRuntime;
+    use fvm_shared::error::ExitCode;
 
-    let caller: u64 = unsafe { fvm_sdk::sys::vm::message_context().unwrap().caller };
+    use super::*;
 
-    assert_eq!(caller, current_state.admin.id().unwrap(), "Only administrator can set whitelist");
+    const ADMIN: Address = Address::new_id(101);
 
-    let (address, whitelist): (Address, bool) = utils::deserialize_params(input);
+    fn construct() -> MockRuntime {
+        let rt = MockRuntime::new(100);
+        Constructor(&rt, ConstructorParams { admin: ADMIN }).unwrap();
+        rt
+    }
+
+    #[test]
+    fn constructor_sets_admin() {
+        let rt = construct();
 
-    current_state.whitelist.insert(address, whitelist);
+        let state: ActorState = rt.state().unwrap();
+        assert_eq!(state.admin, ADMIN);
+        assert!(state.whitelist.is_empty());
+    }
 
-    let cid = current_state.save();
-    fvm_sdk::sself::set_root(&cid).unwrap();
-}
\ No newline at end of file
+    #[test]
+    fn set_whitelist_forbidden() {
+        let mut rt = construct();
+        rt.caller = 102;
+
+        let err = SetWhitelist(
+            &rt,
+            SetWhitelistParams {
+                address: Address::new_id(102),
+                whitelisted: true,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+
+        let state: ActorState = rt.state().unwrap();
+        assert!(state.whitelist.is_empty());
+    }
+
+    #[test]
+    fn is_whitelisted_after_set() {
+        let mut rt = construct();
+        rt.caller = ADMIN.id().unwrap();
+
+        SetWhitelist(
+            &rt,
+            SetWhitelistParams {
+                address: Address::new_id(102),
+                whitelisted: true,
+            },
+        )
+        .unwrap();
+
+        assert!(IsWhitelisted(&rt, Address::new_id(102)).unwrap());
+        assert!(!IsWhitelisted(&rt, Address::new_id(103)).unwrap());
+    }
+
+    #[test]
+    fn set_administrator_once() {
+        let rt = MockRuntime::new(100);
+        let unset = Address::new_id(0);
+        Constructor(&rt, ConstructorParams { admin: unset }).unwrap();
+
+        SetAdministrator(&rt, ADMIN).unwrap();
+        let state: ActorState = rt.state().unwrap();
+        assert_eq!(state.admin, ADMIN);
+
+        let err = SetAdministrator(&rt, Address::new_id(102)).unwrap_err();
+        assert_eq!(err.exit_code(), ExitCode::USR_FORBIDDEN);
+        let state: ActorState = rt.state().unwrap();
+        assert_eq!(state.admin, ADMIN);
+    }
+}
@@ -1,4 +1 @@
-#[cfg(target_arch = "wasm32")]
-mod actor;
-#[cfg(target_arch = "wasm32")]
-mod utils;
+pub mod actor;
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use serde_tuple::{Deserialize_tuple, Serialize_tuple};
+
+pub use fvm_shared::address::Address;
+
+/**************************************************
+ * Actor's state
+ **************************************************/
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, PartialEq, Debug)]
+pub struct ActorState {
+    pub admin: Address,
+    pub whitelist: HashMap<Address, bool>
+}
+
+/**************************************************
+ * Method parameters
+ **************************************************/
+
+/// Parameters of the `Constructor` method.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, PartialEq, Debug)]
+pub struct ConstructorParams {
+    pub admin: Address
+}
+
+/// Parameters of the `SetAdministrator` method.
+pub type SetAdministratorParams = Address;
+
+/// Parameters of the `SetWhitelist` method.
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, PartialEq, Debug)]
+pub struct SetWhitelistParams {
+    pub address: Address,
+    pub whitelisted: bool
+}
+
+/// Parameters of the `IsWhitelisted` method.
+pub type IsWhitelistedParams = Address;
+
+/// Value returned by the `IsWhitelisted` method.
+pub type IsWhitelistedReturn = bool;
@@ -0,0 +1,35 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! The workspace `kythera.toml`, with the build options of the actors and the external actors.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::external::ExternalActor;
+use crate::options::BuildSection;
+
+/// The workspace `kythera.toml` file.
+#[derive(Debug, Default, Deserialize)]
+pub struct WorkspaceConfig {
+    #[serde(default)]
+    pub build: BuildSection,
+    /// Prebuilt target actors from outside the workspace.
+    #[serde(default)]
+    pub external: Vec<ExternalActor>,
+}
+
+impl WorkspaceConfig {
+    /// Load the configuration at `path`, empty if it does not exist.
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+}
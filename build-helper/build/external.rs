@@ -0,0 +1,83 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Prebuilt target actors from outside the workspace, listed in the workspace `kythera.toml`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use kythera_lib::{Abi, Method};
+use serde::Deserialize;
+
+use crate::bundle::load_from_bundle;
+
+/// An external actor, read either from a wasm file or from a CAR bundle.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ExternalActor {
+    /// Name of the artifacts of the actor, e.g. `Token` for `Token.wasm`.
+    pub name: String,
+    /// Wasm module of the actor, relative to the workspace.
+    pub wasm: Option<PathBuf>,
+    /// CAR bundle holding the actor, relative to the workspace.
+    pub car: Option<PathBuf>,
+    /// Name of the actor in the manifest of the bundle, e.g. `account`.
+    pub actor: Option<String>,
+    /// Whether the actor has a `Constructor` method.
+    #[serde(default)]
+    pub constructor: bool,
+    /// Names of the exported methods of the actor.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Maximum size of the wasm module, in bytes.
+    pub size_budget: Option<usize>,
+}
+
+impl ExternalActor {
+    /// File the actor is read from, relative to the workspace.
+    pub fn source(&self) -> Result<&Path, anyhow::Error> {
+        match (&self.wasm, &self.car, &self.actor) {
+            (Some(wasm), None, None) => Ok(wasm),
+            (None, Some(car), Some(_)) => Ok(car),
+            _ => bail!(
+                "External actor {} must set either wasm, or car and actor",
+                self.name
+            ),
+        }
+    }
+
+    /// Read the wasm module of the actor.
+    pub fn load(&self, workspace_dir: &Path) -> Result<Vec<u8>, anyhow::Error> {
+        let path = workspace_dir.join(self.source()?);
+        match &self.actor {
+            Some(actor) => load_from_bundle(&path, actor),
+            None => fs::read(&path)
+                .with_context(|| format!("Could not read wasm file {}", path.display())),
+        }
+    }
+
+    /// Abi of the actor, from its declared methods.
+    pub fn abi(&self) -> Result<Abi, anyhow::Error> {
+        let methods = self
+            .methods
+            .iter()
+            .map(|method| {
+                Method::new_from_name(method).with_context(|| {
+                    format!(
+                        "Could not generate Method for method {} of external actor {}",
+                        method, self.name
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Abi {
+            constructor: self
+                .constructor
+                .then(|| Method::new_from_name("Constructor").unwrap()),
+            set_up: None,
+            methods,
+        })
+    }
+}
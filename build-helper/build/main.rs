@@ -0,0 +1,506 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+#[path = "../src/bundle.rs"]
+mod bundle;
+mod config;
+mod external;
+#[path = "../src/options.rs"]
+mod options;
+#[path = "../src/size.rs"]
+mod size;
+#[path = "../src/validate.rs"]
+mod validate;
+
+use actor_codegen::metadata::KytheraMetadata;
+use actor_codegen::{abi, package_name};
+use anyhow::{bail, Context};
+use convert_case::{Case, Casing};
+use kythera_lib::{self, Abi, Method};
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::{fs, thread};
+
+use crate::config::WorkspaceConfig;
+use crate::external::ExternalActor;
+use crate::options::{target_name, variant_builds, BuildConfig, Kind, DEFAULT_VARIANT};
+
+const FILES_TO_WATCH: &[&str] = &[
+    "Cargo.toml",
+    "src",
+    "../kythera.toml",
+    "../actor-codegen",
+    "../actor-utils",
+    "../actors",
+    "../tests",
+    "../artifacts",
+];
+
+/// A build of an actor, or of one of its variants.
+struct Build<'a> {
+    /// Index of the actor in the built actors.
+    actor: usize,
+    variant: Option<&'a str>,
+    config: BuildConfig,
+}
+
+/// An actor crate with the name and its path.
+struct ActorCrate {
+    name: String,
+    source: PathBuf,
+    metadata: KytheraMetadata,
+}
+
+impl ActorCrate {
+    /// Create a new `ActorCrate` from an input location.
+    fn new_from_path(path: &Path) -> Result<Self, anyhow::Error> {
+        let root = fs::read_dir(path)
+            .with_context(|| format!("Could not read path {}", path.display()))?;
+
+        let cargo_path = root
+            .filter_map(|e| e.ok())
+            .map(|f| f.path())
+            .find(|f| f.ends_with("Cargo.toml"))
+            .with_context(|| {
+                format!(
+                    "path {} doesn't have a valid Cargo.toml file",
+                    path.display()
+                )
+            })?;
+
+        let name = package_name(&cargo_path)?;
+        let metadata = KytheraMetadata::read(&cargo_path)?;
+
+        Ok(Self {
+            name,
+            source: path.join("src/actor.rs"),
+            metadata,
+        })
+    }
+}
+
+/// Build the given actor packages to wasm with `config`, in `target_dir`.
+///
+/// Debug builds keep the name section and DWARF of the modules, with the same profile otherwise.
+fn cargo_build(
+    cargo: &OsStr,
+    manifest_dir: &Path,
+    target_dir: &Path,
+    actor_names: &[&str],
+    config: &BuildConfig,
+    debug: bool,
+) -> Result<(), anyhow::Error> {
+    // Cargo build command for all actors sharing this configuration at once.
+    let mut cmd = Command::new(cargo);
+    cmd.arg("build")
+        .args(actor_names.iter().map(|pkg| "-p=".to_owned() + pkg))
+        .arg("--target=wasm32-unknown-unknown")
+        .arg(format!("--profile={}", config.profile))
+        .arg("--manifest-path=".to_owned() + manifest_dir.join("../Cargo.toml").to_str().unwrap())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // We are supposed to only generate artifacts under OUT_DIR,
+        // so set OUT_DIR as the target directory for this build.
+        .env("CARGO_TARGET_DIR", target_dir)
+        // As we are being called inside a build-script, this env variable is set. However, we set
+        // our own `RUSTFLAGS` and thus, we need to remove this. Otherwise cargo favors this
+        // env variable.
+        .env_remove("CARGO_ENCODED_RUSTFLAGS");
+
+    if config.locked {
+        cmd.arg("--locked");
+    }
+    if config.offline {
+        cmd.arg("--offline");
+    }
+    if !config.features.is_empty() {
+        // Features of several packages have to be qualified by their package.
+        let features = actor_names
+            .iter()
+            .flat_map(|pkg| config.features.iter().map(move |f| format!("{pkg}/{f}")))
+            .collect::<Vec<_>>();
+        cmd.arg(format!("--features={}", features.join(",")));
+    }
+    match &config.rustflags {
+        Some(rustflags) => cmd.env("RUSTFLAGS", rustflags),
+        None => cmd.env_remove("RUSTFLAGS"),
+    };
+    if debug {
+        cmd.arg("--config")
+            .arg(format!("profile.{}.strip=false", config.profile))
+            .arg("--config")
+            .arg(format!("profile.{}.debug=true", config.profile));
+    }
+
+    // Print out the command line we're about to run.
+    println!("cargo:warning=cmd={:?}", &cmd);
+
+    // Launch the command.
+    let mut child = cmd.spawn().context("failed to launch cargo build")?;
+
+    // Pipe the output as cargo warnings. Unfortunately this is the only way to
+    // get cargo build to print the output.
+    let stdout = child.stdout.take().context("Stdout is not available")?;
+    let stderr = child.stderr.take().context("Stderr is not available")?;
+    let j1 = thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            println!("cargo:warning={:?}", line.unwrap());
+        }
+    });
+    let j2 = thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            println!("cargo:warning={:?}", line.unwrap());
+        }
+    });
+
+    j1.join().unwrap();
+    j2.join().unwrap();
+
+    let result = child.wait().context("failed to wait for build to finish")?;
+    if !result.success() {
+        bail!("actor build failed");
+    }
+    Ok(())
+}
+
+/// Generate wasm actors for the input dir.
+fn generate_actors(kind: Kind, clean_artifacts_dir: bool) -> Result<(), anyhow::Error> {
+    let out_dir = std::env::var_os("OUT_DIR")
+        .as_ref()
+        .map(Path::new)
+        .map(|p| p.join("bundle"))
+        .context("no OUT_DIR env var")?;
+    println!("cargo:warning=out_dir: {:?}", &out_dir);
+
+    let manifest_dir =
+        Path::new(&std::env::var_os("CARGO_MANIFEST_DIR").context("CARGO_MANIFEST_DIR unset")?)
+            .to_path_buf();
+
+    let workspace_dir = &manifest_dir
+        .parent()
+        .context("Workspace doesn't exist exist")?;
+
+    let artifacts_dir = workspace_dir.join("artifacts");
+
+    // Cargo executable location.
+    let cargo = std::env::var_os("CARGO").context("no CARGO env var")?;
+
+    // Debug artifacts keep the name section and DWARF to symbolicate traps.
+    let debug_artifacts = std::env::var_os("KYTHERA_DEBUG_ARTIFACTS").is_some();
+
+    let path = match kind {
+        Kind::Target => workspace_dir.join("actors"),
+        Kind::Test => workspace_dir.join("tests"),
+    };
+
+    let actors = fs::read_dir(&path)
+        .with_context(|| format!("Could not read dir {}", path.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|e| e.is_dir())
+        .filter(|e| {
+            fs::read_dir(e)
+                .unwrap()
+                .filter_map(|e| e.ok())
+                .any(|f| f.path().ends_with("Cargo.toml"))
+        })
+        .map(|p| {
+            ActorCrate::new_from_path(&p)
+                .with_context(|| format!("Invalid actor crate {}", p.display()))
+        })
+        .collect::<Result<Vec<ActorCrate>, _>>()?;
+
+    // Every actor is built with its configuration, and once more per variant with the features of
+    // the variant added.
+    let workspace_config = WorkspaceConfig::load(&workspace_dir.join("kythera.toml"))?;
+    let mut builds = Vec::new();
+    for (index, actor) in actors.iter().enumerate() {
+        let config = workspace_config.build.config(&kind, &actor.metadata.build);
+        let variants = variant_builds(&kind, &actor.name, &config, &actor.metadata.variants)?;
+        for (variant, config) in variants {
+            builds.push(Build {
+                actor: index,
+                variant,
+                config,
+            });
+        }
+    }
+
+    // Builds sharing a configuration are made at once, as long as they are of different actors:
+    // each build of an actor overwrites its wasm file.
+    let mut groups: Vec<Vec<&Build>> = Vec::new();
+    for build in &builds {
+        let group = groups.iter_mut().find(|group| {
+            group[0].config == build.config && group.iter().all(|b| b.actor != build.actor)
+        });
+        match group {
+            Some(group) => group.push(build),
+            None => groups.push(vec![build]),
+        }
+    }
+
+    // Create artifacts dir.
+    if let Err(err) = fs::create_dir(&artifacts_dir) {
+        match err.kind() {
+            std::io::ErrorKind::AlreadyExists => {
+                if clean_artifacts_dir {
+                    fs::remove_dir_all(&artifacts_dir).context("Could not remove artifacts dir")?;
+                    fs::create_dir(&artifacts_dir).context("Could not create artifacts dir")?;
+                }
+            }
+            err => bail!("Could not create artifacts dir {err}"),
+        }
+    };
+
+    let debug_dir = out_dir.with_file_name("bundle-debug");
+    let debug_dir = debug_artifacts.then_some(debug_dir.as_path());
+    for group in &groups {
+        let config = &group[0].config;
+        let actor_names = group
+            .iter()
+            .map(|build| actors[build.actor].name.as_str())
+            .collect::<Vec<&str>>();
+
+        cargo_build(&cargo, &manifest_dir, &out_dir, &actor_names, config, false)?;
+        if let Some(debug_dir) = debug_dir {
+            cargo_build(&cargo, &manifest_dir, debug_dir, &actor_names, config, true)?;
+        }
+
+        // Create the Abi files and copy the wasm files to the artifacts dir.
+        for build in group {
+            publish_actor(
+                &kind,
+                &actors[build.actor],
+                build.variant,
+                config,
+                &out_dir,
+                debug_dir,
+                &artifacts_dir,
+            )?;
+        }
+    }
+
+    if let Kind::Target = kind {
+        for external in &workspace_config.external {
+            publish_external(external, workspace_dir, &artifacts_dir)?;
+        }
+    }
+    Ok(())
+}
+
+/// Validate a wasm actor and report its size, failing if it exceeds its size budget.
+///
+/// Function sizes are named after the `debug` build of the actor, if there is one.
+fn check_wasm(
+    name: &str,
+    wasm: &[u8],
+    debug: Option<&[u8]>,
+    size_budget: Option<usize>,
+) -> Result<(), anyhow::Error> {
+    validate::validate_module(wasm)
+        .with_context(|| format!("Actor {name} is not a valid FVM actor"))?;
+
+    let size = size::analyze(wasm, debug)
+        .with_context(|| format!("Could not analyze size of actor {name}"))?;
+    for line in format!("{name} size: {size}").lines() {
+        println!("cargo:warning={line}");
+    }
+    if let Some(budget) = size_budget {
+        if size.total > budget {
+            bail!(
+                "Actor {name} is {} bytes, over its size budget of {budget} bytes",
+                size.total
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Validate an external actor and write its wasm and Abi files to the artifacts dir.
+fn publish_external(
+    external: &ExternalActor,
+    workspace_dir: &Path,
+    artifacts_dir: &Path,
+) -> Result<(), anyhow::Error> {
+    println!(
+        "cargo:rerun-if-changed={}",
+        workspace_dir.join(external.source()?).display()
+    );
+
+    let wasm_file = artifacts_dir.join(format!("{}.wasm", external.name));
+    if wasm_file.exists() {
+        bail!(
+            "External actor {} has the name of a workspace actor",
+            external.name
+        );
+    }
+
+    let wasm = external.load(workspace_dir)?;
+    check_wasm(&external.name, &wasm, None, external.size_budget)?;
+
+    fs::write(&wasm_file, &wasm).with_context(|| {
+        format!(
+            "Could not write wasm file of external actor {}",
+            external.name
+        )
+    })?;
+    fs::write(
+        artifacts_dir.join(format!("{}.cbor", external.name)),
+        kythera_lib::to_vec(&external.abi()?).unwrap(),
+    )
+    .with_context(|| {
+        format!(
+            "Could not write Abi file of external actor {}",
+            external.name
+        )
+    })
+}
+
+/// Validate a built actor and copy it along with its Abi and metadata to the artifacts dir.
+fn publish_actor(
+    kind: &Kind,
+    actor: &ActorCrate,
+    variant: Option<&str>,
+    config: &BuildConfig,
+    out_dir: &Path,
+    debug_dir: Option<&Path>,
+    artifacts_dir: &Path,
+) -> Result<(), anyhow::Error> {
+    let actor_methods = abi::parse_methods(&actor.source)?;
+
+    let constructor = actor_methods
+        .constructor
+        .then(|| Method::new_from_name("Constructor").unwrap());
+    let set_up = actor_methods
+        .set_up
+        .then(|| Method::new_from_name("SetUp").unwrap());
+    let methods = actor_methods
+        .methods
+        .iter()
+        .map(|method| {
+            Method::new_from_name(method).with_context(|| {
+                format!(
+                    "Could not generate Method for method {} of actor {}",
+                    method,
+                    actor.source.display()
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let abi = Abi {
+        constructor,
+        set_up,
+        methods,
+    };
+
+    let display_name = match variant {
+        Some(variant) => format!("{} ({variant})", actor.name),
+        None => actor.name.clone(),
+    };
+
+    let actor_wasm_file = out_dir.join(format!(
+        "wasm32-unknown-unknown/{}/{}.wasm",
+        config.profile_dir(),
+        // Cargo replaces -'s for _'s on compilation targets.
+        &actor.name.replace("-", "_")
+    ));
+
+    let wasm = fs::read(&actor_wasm_file)
+        .with_context(|| format!("Could not read wasm file {}", actor_wasm_file.display()))?;
+    let debug_wasm_file = debug_dir.map(|debug_dir| {
+        debug_dir.join(format!(
+            "wasm32-unknown-unknown/{}/{}.wasm",
+            config.profile_dir(),
+            &actor.name.replace('-', "_")
+        ))
+    });
+    let debug_wasm = debug_wasm_file
+        .as_ref()
+        .map(|file| {
+            fs::read(file)
+                .with_context(|| format!("Could not read debug wasm file {}", file.display()))
+        })
+        .transpose()?;
+    check_wasm(
+        &display_name,
+        &wasm,
+        debug_wasm.as_deref(),
+        actor.metadata.size_budget,
+    )?;
+
+    // If the Actor is a test actor we rename the trailing `-test` to `.t`.
+    let actor_dest_name = match kind {
+        Kind::Target => actor.name.clone(),
+        Kind::Test => match actor.name.rfind("test") {
+            Some(index) => {
+                let (before, _after) = actor.name.split_at(index);
+                format!("{}.t", before)
+            }
+            None => bail!(format!(
+                "{} actor should be a test actor, but doesn't have test in its name",
+                actor.name
+            )),
+        },
+    }
+    .to_case(Case::Pascal);
+
+    // Variants are published as `<Name>-<variant>`.
+    let actor_dest_name = target_name(&actor_dest_name, variant.unwrap_or(DEFAULT_VARIANT));
+
+    fs::copy(
+        &actor_wasm_file,
+        artifacts_dir.join(format!("{actor_dest_name}.wasm")),
+    )
+    .with_context(|| {
+        format!(
+            "Could not copy {} wasm file to artifacts dir",
+            &actor_wasm_file.display()
+        )
+    })?;
+
+    if let Some(debug_wasm_file) = debug_wasm_file {
+        fs::copy(
+            &debug_wasm_file,
+            artifacts_dir.join(format!("{actor_dest_name}.debug.wasm")),
+        )
+        .with_context(|| {
+            format!(
+                "Could not copy {} debug wasm file to artifacts dir",
+                &debug_wasm_file.display()
+            )
+        })?;
+    }
+
+    let mut abi_file = File::create(artifacts_dir.join(format!("{actor_dest_name}.cbor"))).unwrap();
+    abi_file
+        .write_all(&kythera_lib::to_vec(&abi).unwrap())
+        .with_context(|| {
+            format!(
+                "Could not generate Abi file for actor {}",
+                actor.source.display()
+            )
+        })?;
+
+    if !actor.metadata.is_empty() {
+        actor
+            .metadata
+            .write(&artifacts_dir.join(format!("{actor_dest_name}.toml")))?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    for file in FILES_TO_WATCH {
+        println!("cargo:rerun-if-changed={}", file);
+    }
+    println!("cargo:rerun-if-env-changed=KYTHERA_DEBUG_ARTIFACTS");
+
+    generate_actors(Kind::Target, true).expect("Could not generate target actors");
+    generate_actors(Kind::Test, false).expect("Could not generate test actors");
+
+    Ok(())
+}
@@ -0,0 +1,258 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Loading of the target and test actors generated by the build script.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use actor_codegen::metadata::KytheraMetadata;
+use anyhow::{bail, Context};
+use convert_case::{Case, Casing};
+use kythera_lib::{Abi, WasmActor};
+
+use crate::options::target_name;
+
+/// Extension of the test actors artifacts, `<Target>.t.wasm`.
+const TEST_SUFFIX: &str = ".t";
+
+/// The `artifacts` directory of the workspace.
+pub fn artifacts_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../artifacts")
+}
+
+/// A target actor deployed for a test actor.
+#[derive(Clone)]
+pub struct Target {
+    /// Name the test actor looks the target up with.
+    pub name: String,
+    pub actor: WasmActor,
+    /// DAG-CBOR parameters of the target constructor.
+    pub constructor_params: Option<Vec<u8>>,
+}
+
+/// A test actor and the target actors it tests.
+#[derive(Clone)]
+pub struct ActorPair {
+    /// Name of the tested target, or of the test actor when it declares its targets.
+    pub name: String,
+    /// The target named after the test actor, or the ones it declares.
+    pub targets: Vec<Target>,
+    /// Whether the test actor declares its targets, its methods getting them all by name.
+    pub named_targets: bool,
+    pub test: WasmActor,
+    /// Kythera metadata of the test actor.
+    pub metadata: KytheraMetadata,
+    /// Debug modules of the target and test actors, by actor name, when they were built.
+    pub debug: Vec<(String, Vec<u8>)>,
+}
+
+/// Load the actor named `name` from its wasm and Abi files in `dir`.
+pub fn load_actor(dir: &Path, name: &str) -> Result<WasmActor, anyhow::Error> {
+    let wasm_path = dir.join(format!("{name}.wasm"));
+    let bytecode = fs::read(&wasm_path)
+        .with_context(|| format!("Could not read wasm file {}", wasm_path.display()))?;
+
+    let abi_path = dir.join(format!("{name}.cbor"));
+    let abi: Abi = fs::read(&abi_path)
+        .with_context(|| format!("Could not read Abi file {}", abi_path.display()))
+        .and_then(|abi| {
+            kythera_lib::from_slice(&abi)
+                .with_context(|| format!("Could not decode Abi file {}", abi_path.display()))
+        })?;
+
+    Ok(WasmActor::new(name.to_string(), bytecode, abi))
+}
+
+/// Load the debug module of the actor named `name` in `dir`, if it was built.
+pub fn load_debug(dir: &Path, name: &str) -> Result<Option<Vec<u8>>, anyhow::Error> {
+    let path = dir.join(format!("{name}.debug.wasm"));
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read(&path)
+        .map(Some)
+        .with_context(|| format!("Could not read debug wasm file {}", path.display()))
+}
+
+/// Load every test actor in `dir` along with its target actor, once per target variant it runs
+/// against.
+pub fn load_pairs(dir: &Path) -> Result<Vec<ActorPair>, anyhow::Error> {
+    let mut tests = fs::read_dir(dir)
+        .with_context(|| format!("Could not read artifacts dir {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            e.file_name()
+                .to_str()
+                .and_then(|name| name.strip_suffix(".wasm"))
+                .filter(|name| name.ends_with(TEST_SUFFIX))
+                .map(str::to_string)
+        })
+        .collect::<Vec<String>>();
+    tests.sort();
+
+    let mut pairs = Vec::new();
+    for test in &tests {
+        let metadata = KytheraMetadata::load(&dir.join(format!("{test}.toml")))?;
+        let test_actor = load_actor(dir, test)?;
+        check_expected_failures(&test_actor, &metadata)?;
+        let base = test.trim_end_matches(TEST_SUFFIX);
+
+        // A test actor declaring its targets is a single pair, otherwise it is one per variant of
+        // the target named after it.
+        let pair_targets = if metadata.targets.is_empty() {
+            variant_targets(base, &metadata.target_variants)
+                .into_iter()
+                .map(|name| {
+                    let target = Target {
+                        name: name.to_case(Case::Snake),
+                        actor: load_actor(dir, &name)
+                            .with_context(|| format!("Could not load target actor of {test}"))?,
+                        constructor_params: metadata.decode_constructor_params()?,
+                    };
+                    Ok((name, vec![target]))
+                })
+                .collect::<Result<Vec<_>, anyhow::Error>>()?
+        } else {
+            if !metadata.target_variants.is_empty() || metadata.constructor_params.is_some() {
+                bail!("{test} declares its targets, along with options of a single target");
+            }
+            let targets = metadata
+                .targets
+                .iter()
+                .map(|(name, target)| {
+                    Ok(Target {
+                        name: name.clone(),
+                        actor: load_actor(dir, &target.actor).with_context(|| {
+                            format!("Could not load target actor {name} of {test}")
+                        })?,
+                        constructor_params: target.decode_constructor_params()?,
+                    })
+                })
+                .collect::<Result<Vec<_>, anyhow::Error>>()?;
+            vec![(base.to_string(), targets)]
+        };
+
+        let named_targets = !metadata.targets.is_empty();
+        for (name, targets) in pair_targets {
+            let mut debug = Vec::new();
+            let names = targets
+                .iter()
+                .map(|target| target.actor.name())
+                .chain([test.as_str()]);
+            for name in names {
+                if let Some(wasm) = load_debug(dir, name)? {
+                    debug.push((name.to_string(), wasm));
+                }
+            }
+
+            pairs.push(ActorPair {
+                name,
+                targets,
+                named_targets,
+                test: test_actor.clone(),
+                metadata: metadata.clone(),
+                debug,
+            });
+        }
+    }
+    Ok(pairs)
+}
+
+/// Check that the expected failures of the metadata of `test` are declared for its methods.
+fn check_expected_failures(
+    test: &WasmActor,
+    metadata: &KytheraMetadata,
+) -> Result<(), anyhow::Error> {
+    for name in metadata.expect.keys() {
+        if !test
+            .abi()
+            .methods
+            .iter()
+            .any(|method| method.name() == name)
+        {
+            bail!(
+                "{} expects a failure of {name}, which is not one of its methods",
+                test.name()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Artifact names of the targets a test actor of `base` runs against, the default build of `base`
+/// unless it binds `variants`.
+fn variant_targets(base: &str, variants: &[String]) -> Vec<String> {
+    match variants {
+        [] => vec![base.to_string()],
+        variants => variants
+            .iter()
+            .map(|variant| target_name(base, variant))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use actor_codegen::metadata::ExpectedFailure;
+    use kythera_lib::Method;
+
+    use super::*;
+
+    fn test_actor(methods: &[&str]) -> WasmActor {
+        let abi = Abi {
+            constructor: None,
+            set_up: None,
+            methods: methods
+                .iter()
+                .map(|name| Method::new_from_name(name).unwrap())
+                .collect(),
+        };
+        WasmActor::new(String::from("Whitelist.t"), Vec::new(), abi)
+    }
+
+    fn expecting(name: &str) -> KytheraMetadata {
+        KytheraMetadata {
+            expect: BTreeMap::from([(
+                name.to_string(),
+                ExpectedFailure {
+                    exit_code: 24,
+                    message: None,
+                },
+            )]),
+            ..KytheraMetadata::default()
+        }
+    }
+
+    #[test]
+    fn targets_of_variants() {
+        let variants = [String::from("default"), String::from("strict")];
+
+        assert_eq!(variant_targets("Whitelist", &[]), ["Whitelist"]);
+        assert_eq!(
+            variant_targets("Whitelist", &variants),
+            ["Whitelist", "Whitelist-strict"]
+        );
+    }
+
+    #[test]
+    fn expected_failures_of_test_methods() {
+        let test = test_actor(&["TestFailNotAdmin", "TestHappyPath"]);
+
+        assert!(check_expected_failures(&test, &expecting("TestFailNotAdmin")).is_ok());
+        assert!(check_expected_failures(&test, &KytheraMetadata::default()).is_ok());
+    }
+
+    #[test]
+    fn expected_failures_of_unknown_methods_err() {
+        let test = test_actor(&["TestFailNotAdmin"]);
+        let err = check_expected_failures(&test, &expecting("TestFailNotAdmn")).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Whitelist.t expects a failure of TestFailNotAdmn, which is not one of its methods"
+        );
+    }
+}
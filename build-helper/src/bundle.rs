@@ -0,0 +1,135 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Reading of actors from CAR bundles, such as the bundles of the builtin actors.
+//!
+//! Also compiled into the build script, which reads the external actors from their bundle.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use cid::Cid;
+use fvm_ipld_blockstore::{Blockstore, MemoryBlockstore};
+use fvm_ipld_encoding::CborStore;
+
+/// Read the code of the actor named `actor` in the CAR bundle at `path`.
+///
+/// The root of a bundle is its manifest, `(version, data)`, where `data` lists the code CID of
+/// each actor by name.
+pub fn load_from_bundle(path: &Path, actor: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let car =
+        fs::read(path).with_context(|| format!("Could not read CAR bundle {}", path.display()))?;
+    let store = MemoryBlockstore::new();
+    let roots = futures::executor::block_on(fvm_ipld_car::load_car_unchecked(&store, &*car))
+        .with_context(|| format!("Could not load CAR bundle {}", path.display()))?;
+
+    let root = match roots.as_slice() {
+        [root] => root,
+        _ => bail!("CAR bundle {} must have a single root", path.display()),
+    };
+    let (version, data): (u32, Cid) = store
+        .get_cbor(root)?
+        .with_context(|| format!("Missing manifest of CAR bundle {}", path.display()))?;
+    if version != 1 {
+        bail!(
+            "Unsupported manifest version {version} of CAR bundle {}",
+            path.display()
+        );
+    }
+    let entries: Vec<(String, Cid)> = store
+        .get_cbor(&data)?
+        .with_context(|| format!("Missing manifest data of CAR bundle {}", path.display()))?;
+
+    let code = entries
+        .iter()
+        .find(|(name, _)| name == actor)
+        .map(|(_, code)| code)
+        .with_context(|| format!("No actor {actor} in CAR bundle {}", path.display()))?;
+    store.get(code)?.with_context(|| {
+        format!(
+            "Missing code of actor {actor} in CAR bundle {}",
+            path.display()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use cid::multihash::Code;
+    use fvm_ipld_blockstore::Block;
+    use fvm_ipld_car::CarHeader;
+    use fvm_ipld_encoding::IPLD_RAW;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Write a bundle of the given actors, with a manifest of the given version, in `dir`.
+    fn write_bundle(dir: &TempDir, version: u32, actors: &[(&str, &[u8])]) -> PathBuf {
+        let store = MemoryBlockstore::new();
+        let entries = actors
+            .iter()
+            .map(|(name, code)| {
+                let cid = store
+                    .put(Code::Blake2b256, &Block::new(IPLD_RAW, code))
+                    .unwrap();
+                (name.to_string(), cid)
+            })
+            .collect::<Vec<_>>();
+        let data = store.put_cbor(&entries, Code::Blake2b256).unwrap();
+        let manifest = store.put_cbor(&(version, data), Code::Blake2b256).unwrap();
+
+        let blocks = entries
+            .iter()
+            .map(|(_, cid)| cid)
+            .chain([&data, &manifest])
+            .map(|cid| (*cid, store.get(cid).unwrap().unwrap()))
+            .collect::<Vec<_>>();
+        let mut car = Vec::new();
+        futures::executor::block_on(
+            CarHeader::new(vec![manifest], 1)
+                .write_stream_async(&mut car, &mut futures::stream::iter(blocks)),
+        )
+        .unwrap();
+
+        let path = dir.path().join("bundle.car");
+        fs::write(&path, car).unwrap();
+        path
+    }
+
+    #[test]
+    fn load_actor_code() {
+        let dir = TempDir::new().unwrap();
+        let path = write_bundle(&dir, 1, &[("account", b"account code"), ("init", b"init")]);
+
+        assert_eq!(load_from_bundle(&path, "account").unwrap(), b"account code");
+        assert_eq!(load_from_bundle(&path, "init").unwrap(), b"init");
+    }
+
+    #[test]
+    fn load_missing_actor_fails() {
+        let dir = TempDir::new().unwrap();
+        let path = write_bundle(&dir, 1, &[("account", b"account code")]);
+
+        assert_eq!(
+            load_from_bundle(&path, "multisig").unwrap_err().to_string(),
+            format!("No actor multisig in CAR bundle {}", path.display())
+        );
+    }
+
+    #[test]
+    fn load_unsupported_manifest_fails() {
+        let dir = TempDir::new().unwrap();
+        let path = write_bundle(&dir, 2, &[("account", b"account code")]);
+
+        assert_eq!(
+            load_from_bundle(&path, "account").unwrap_err().to_string(),
+            format!(
+                "Unsupported manifest version 2 of CAR bundle {}",
+                path.display()
+            )
+        );
+    }
+}
@@ -0,0 +1,467 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Host side environment to write Kythera tests as `#[test]` functions, without a test actor.
+//!
+//! ```ignore
+//! let mut env = TestEnv::new()?;
+//! let whitelist = env.deploy("Whitelist")?;
+//!
+//! env.call(whitelist, "SetWhitelist", &params)?
+//!     .assert_exit_code(ExitCode::USR_FORBIDDEN);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+use actor_codegen::accounts::{self, NamedAccount};
+use actor_utils::cheatcodes::CHEATCODES_ACTOR_ID;
+use anyhow::{bail, Context};
+use cid::multihash::Code;
+use cid::Cid;
+use fvm::executor::ApplyRet;
+use fvm_ipld_encoding::de::DeserializeOwned;
+use fvm_ipld_encoding::{CborStore, RawBytes};
+use fvm_shared::address::Address;
+use fvm_shared::bigint::Zero;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use fvm_shared::error::ExitCode;
+use fvm_shared::message::Message;
+use fvm_shared::{ActorID, MethodNum, METHOD_SEND};
+use kythera_lib::{Method, Tester, WasmActor};
+use serde::ser;
+
+use crate::artifacts::{artifacts_dir, load_actor};
+use crate::runner::tester;
+
+/// Gas limit of the messages sent by the environment.
+const GAS_LIMIT: u64 = 10_000_000_000;
+
+/// Result of a message executed by the [`TestEnv`].
+#[derive(Debug, Clone)]
+pub struct CallResult {
+    pub exit_code: ExitCode,
+    pub return_data: RawBytes,
+    pub gas_used: u64,
+    /// Abort message of the call, if it failed.
+    pub message: Option<String>,
+}
+
+impl CallResult {
+    /// Panic if the call did not exit with `exit_code`.
+    #[track_caller]
+    pub fn assert_exit_code(&self, exit_code: ExitCode) -> &Self {
+        if self.exit_code != exit_code {
+            panic!(
+                "expected exit code {}, got {}{}",
+                exit_code.value(),
+                self.exit_code.value(),
+                self.message
+                    .as_ref()
+                    .map(|msg| format!(": {msg}"))
+                    .unwrap_or_default()
+            );
+        }
+        self
+    }
+
+    /// Panic if the call did not succeed.
+    #[track_caller]
+    pub fn assert_success(&self) -> &Self {
+        self.assert_exit_code(ExitCode::OK)
+    }
+
+    /// Decode the return value of the call, panicking if it can't be.
+    #[track_caller]
+    pub fn decode<R: DeserializeOwned>(&self) -> R {
+        match self.return_data.deserialize() {
+            Ok(ret) => ret,
+            Err(err) => panic!("could not decode return value: {err}"),
+        }
+    }
+
+    /// Panic if the call did not succeed with a return value equal to `expected`.
+    #[track_caller]
+    pub fn assert_return_eq<R: DeserializeOwned + PartialEq + Debug>(&self, expected: R) {
+        self.assert_success();
+        assert_eq!(self.decode::<R>(), expected);
+    }
+}
+
+/// A named test account created by the [`TestEnv`].
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub name: String,
+    pub id: ActorID,
+    /// Robust address of the account.
+    pub address: Address,
+    /// secp256k1 secret key of the account.
+    pub secret_key: [u8; 32],
+}
+
+/// A Kythera tester along with an account sending the messages, and the named test accounts of
+/// the workspace.
+pub struct TestEnv {
+    tester: Tester,
+    artifacts_dir: PathBuf,
+    sender: ActorID,
+    /// Account sending the next message in place of `sender`, see [`TestEnv::prank`].
+    prank: Option<ActorID>,
+    /// Next sequence of each account sending messages.
+    sequences: HashMap<ActorID, u64>,
+    accounts: Vec<Account>,
+}
+
+impl TestEnv {
+    /// Create an environment deploying actors from the workspace `artifacts` dir.
+    ///
+    /// The named accounts of the workspace `kythera.toml` are created right after the sender, in
+    /// the order of their names, at the IDs generated for them.
+    pub fn new() -> Result<Self, anyhow::Error> {
+        let mut tester = tester();
+        let sender = tester
+            .create_account()
+            .context("Could not create sender account")?;
+
+        let mut env = Self {
+            tester,
+            artifacts_dir: artifacts_dir(),
+            sender,
+            prank: None,
+            sequences: HashMap::new(),
+            accounts: Vec::new(),
+        };
+        let workspace_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        for account in accounts::load(&workspace_dir)? {
+            env.create_account(account)?;
+        }
+        Ok(env)
+    }
+
+    /// Create the account actor of a named account by sending to its robust address, and deal it
+    /// its starting balance.
+    fn create_account(&mut self, account: NamedAccount) -> Result<(), anyhow::Error> {
+        let ret = self.apply_message(self.sender, account.address, METHOD_SEND, None)?;
+        if !ret.msg_receipt.exit_code.is_success() {
+            bail!(
+                "Creation of account {} exited with code {:?}",
+                account.name,
+                ret.msg_receipt.exit_code
+            );
+        }
+        let id = self
+            .tester
+            .state_tree()
+            .lookup_id(&account.address)
+            .with_context(|| format!("Could not resolve account {}", account.name))?
+            .with_context(|| format!("Account {} was not created", account.name))?;
+        if id != account.id {
+            bail!(
+                "Account {} was created at {id} instead of {}, the named accounts must be created \
+                 before any other actor",
+                account.name,
+                account.id
+            );
+        }
+
+        if !account.balance.is_zero() {
+            self.deal(Address::new_id(id), account.balance)?;
+        }
+        self.accounts.push(Account {
+            name: account.name,
+            id,
+            address: account.address,
+            secret_key: account.secret_key,
+        });
+        Ok(())
+    }
+
+    /// The directory actors are deployed from by name.
+    pub fn artifacts_dir(&self) -> &Path {
+        &self.artifacts_dir
+    }
+
+    /// The account sending the messages.
+    pub fn sender(&self) -> ActorID {
+        self.sender
+    }
+
+    /// The named account `name`.
+    pub fn account(&self, name: &str) -> Result<&Account, anyhow::Error> {
+        self.accounts
+            .iter()
+            .find(|account| account.name == name)
+            .with_context(|| format!("No account {name} in kythera.toml"))
+    }
+
+    /// The named accounts, in the order of their names.
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    /// Deploy the actor `name` from the artifacts, calling its constructor if it has one.
+    pub fn deploy(&mut self, name: &str) -> Result<ActorID, anyhow::Error> {
+        let actor = load_actor(&self.artifacts_dir, name)?;
+        self.deploy_actor(&actor, None)
+    }
+
+    /// Deploy the actor `name` from the artifacts, calling its constructor with `params`.
+    pub fn deploy_with_params<P>(
+        &mut self,
+        name: &str,
+        params: &P,
+    ) -> Result<ActorID, anyhow::Error>
+    where
+        P: ser::Serialize + ?Sized,
+    {
+        let actor = load_actor(&self.artifacts_dir, name)?;
+        let params = RawBytes::serialize(params)
+            .with_context(|| format!("Could not encode {name} constructor parameters"))?;
+        self.deploy_actor(&actor, Some(params))
+    }
+
+    /// Deploy `actor`, calling its constructor with `params` if it has one.
+    pub fn deploy_actor(
+        &mut self,
+        actor: &WasmActor,
+        params: Option<RawBytes>,
+    ) -> Result<ActorID, anyhow::Error> {
+        let name = actor.name();
+        let id = self
+            .tester
+            .deploy_actor(actor)
+            .with_context(|| format!("Could not deploy {name}"))?;
+
+        match (&actor.abi().constructor, params) {
+            (Some(constructor), params) => {
+                let ret = self.apply(id, constructor.number(), params)?;
+                if !ret.msg_receipt.exit_code.is_success() {
+                    bail!(
+                        "Constructor of {name} exited with code {:?}{}",
+                        ret.msg_receipt.exit_code,
+                        ret.failure_info
+                            .map(|info| format!(": {info}"))
+                            .unwrap_or_default()
+                    );
+                }
+            }
+            (None, Some(_)) => bail!("{name} has no constructor to pass parameters to"),
+            (None, None) => {}
+        }
+        Ok(id)
+    }
+
+    /// Call `method` on the actor `to` with `params`.
+    pub fn call<P>(
+        &mut self,
+        to: ActorID,
+        method: &str,
+        params: &P,
+    ) -> Result<CallResult, anyhow::Error>
+    where
+        P: ser::Serialize + ?Sized,
+    {
+        let params = RawBytes::serialize(params)
+            .with_context(|| format!("Could not encode {method} parameters"))?;
+        self.send(to, method, Some(params))
+    }
+
+    /// Call `method` on the actor `to` with raw `params`.
+    pub fn send(
+        &mut self,
+        to: ActorID,
+        method: &str,
+        params: Option<RawBytes>,
+    ) -> Result<CallResult, anyhow::Error> {
+        let method_num = Method::new_from_name(method)
+            .with_context(|| format!("Invalid method name {method}"))?
+            .number();
+        let ret = self
+            .apply(to, method_num, params)
+            .with_context(|| format!("Could not execute {method}"))?;
+
+        Ok(CallResult {
+            exit_code: ret.msg_receipt.exit_code,
+            return_data: ret.msg_receipt.return_data,
+            gas_used: ret.msg_receipt.gas_used,
+            message: ret.failure_info.map(|info| info.to_string()),
+        })
+    }
+
+    /// Call `method` on the actor `to` with `params`, impersonating the named account `from`.
+    pub fn call_as<P>(
+        &mut self,
+        from: &str,
+        to: ActorID,
+        method: &str,
+        params: &P,
+    ) -> Result<CallResult, anyhow::Error>
+    where
+        P: ser::Serialize + ?Sized,
+    {
+        let from = self.account(from)?.id;
+        let method_num = Method::new_from_name(method)
+            .with_context(|| format!("Invalid method name {method}"))?
+            .number();
+        let params = RawBytes::serialize(params)
+            .with_context(|| format!("Could not encode {method} parameters"))?;
+        let ret = self
+            .apply_message(from, Address::new_id(to), method_num, Some(params))
+            .with_context(|| format!("Could not execute {method}"))?;
+
+        Ok(CallResult {
+            exit_code: ret.msg_receipt.exit_code,
+            return_data: ret.msg_receipt.return_data,
+            gas_used: ret.msg_receipt.gas_used,
+            message: ret.failure_info.map(|info| info.to_string()),
+        })
+    }
+
+    /// Send a message calling `method_num` on the actor `to`, returning its full execution result.
+    pub fn apply(
+        &mut self,
+        to: ActorID,
+        method_num: MethodNum,
+        params: Option<RawBytes>,
+    ) -> Result<ApplyRet, anyhow::Error> {
+        self.apply_message(self.sender, Address::new_id(to), method_num, params)
+    }
+
+    /// Send a message from the account `from`, or from the pranked account if `from` is the
+    /// environment sender.
+    pub(crate) fn apply_message(
+        &mut self,
+        from: ActorID,
+        to: Address,
+        method_num: MethodNum,
+        params: Option<RawBytes>,
+    ) -> Result<ApplyRet, anyhow::Error> {
+        let from = match self.prank.take() {
+            Some(prank) if from == self.sender => prank,
+            Some(prank) => {
+                self.prank = Some(prank);
+                from
+            }
+            None => from,
+        };
+        let sequence = self.sequences.get(&from).copied().unwrap_or_default();
+        let message = Message {
+            version: 0,
+            from: Address::new_id(from),
+            to,
+            sequence,
+            value: TokenAmount::zero(),
+            method_num,
+            params: params.unwrap_or_default(),
+            gas_limit: GAS_LIMIT,
+            gas_fee_cap: TokenAmount::zero(),
+            gas_premium: TokenAmount::zero(),
+        };
+        let ret = self
+            .tester
+            .execute(message)
+            .with_context(|| format!("Could not execute method {method_num} of {to}"))?;
+        self.sequences.insert(from, sequence + 1);
+        Ok(ret)
+    }
+
+    /// Call the `method` cheatcode with `params`, failing if it was not applied.
+    pub fn cheatcode<P>(&mut self, method: &str, params: &P) -> Result<CallResult, anyhow::Error>
+    where
+        P: ser::Serialize + ?Sized,
+    {
+        let ret = self.call(CHEATCODES_ACTOR_ID, method, params)?;
+        if !ret.exit_code.is_success() {
+            bail!("{method} cheatcode exited with code {:?}", ret.exit_code);
+        }
+        Ok(ret)
+    }
+
+    /// Set the timestamp of the following messages.
+    pub fn warp(&mut self, timestamp: u64) -> Result<(), anyhow::Error> {
+        self.cheatcode("Warp", &timestamp).map(|_| ())
+    }
+
+    /// Set the epoch of the following messages.
+    pub fn epoch(&mut self, epoch: ChainEpoch) -> Result<(), anyhow::Error> {
+        self.cheatcode("Epoch", &epoch).map(|_| ())
+    }
+
+    /// Set the base fee of the following messages.
+    pub fn fee(&mut self, fee: TokenAmount) -> Result<(), anyhow::Error> {
+        self.cheatcode("Fee", &fee).map(|_| ())
+    }
+
+    /// Set the chain id of the following messages.
+    pub fn chain_id(&mut self, chain_id: u64) -> Result<(), anyhow::Error> {
+        self.cheatcode("ChainId", &chain_id).map(|_| ())
+    }
+
+    /// Send the next message of the environment sender from the account at `caller` instead.
+    ///
+    /// Messages applied by the host have no caller distinct from their origin, so there is no
+    /// `trick` counterpart: both are the account sending the message.
+    pub fn prank(&mut self, caller: Address) -> Result<(), anyhow::Error> {
+        let caller = self
+            .tester
+            .state_tree()
+            .lookup_id(&caller)
+            .with_context(|| format!("Could not resolve {caller}"))?
+            .with_context(|| format!("No actor at {caller}"))?;
+        self.prank = Some(caller);
+        Ok(())
+    }
+
+    /// Set the balance of `address` to `amount`.
+    pub fn deal(&mut self, address: Address, amount: TokenAmount) -> Result<(), anyhow::Error> {
+        self.cheatcode("Deal", &(address, amount)).map(|_| ())
+    }
+
+    /// Store `state` and set it as the root state of `target`, returning its `Cid`.
+    pub fn alter_state<T: ser::Serialize>(
+        &mut self,
+        target: ActorID,
+        state: &T,
+    ) -> Result<Cid, anyhow::Error> {
+        let root = self
+            .tester
+            .blockstore()
+            .put_cbor(state, Code::Blake2b256)
+            .with_context(|| format!("Could not store state of {target}"))?;
+        self.cheatcode("Alter", &(Address::new_id(target), root.to_string()))?;
+        Ok(root)
+    }
+
+    /// Read and decode the current root state of `target`, from the head of the actor in the
+    /// state tree.
+    pub fn read_state<T: DeserializeOwned>(&self, target: ActorID) -> Result<T, anyhow::Error> {
+        let root = self
+            .tester
+            .state_tree()
+            .get_actor(target)
+            .with_context(|| format!("Could not get actor {target}"))?
+            .with_context(|| format!("No actor {target}"))?
+            .state;
+
+        self.tester
+            .blockstore()
+            .get_cbor(&root)
+            .with_context(|| format!("Could not load state of {target}"))?
+            .with_context(|| format!("State {root} of {target} not found"))
+    }
+
+    /// Panic if the state of `target` does not decode to `expected`.
+    #[track_caller]
+    pub fn assert_state_eq<T: DeserializeOwned + PartialEq + Debug>(
+        &self,
+        target: ActorID,
+        expected: &T,
+    ) {
+        match self.read_state::<T>(target) {
+            Ok(state) => assert_eq!(&state, expected),
+            Err(err) => panic!("could not read state of {target}: {err:#}"),
+        }
+    }
+}
@@ -1 +1,17 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
 
+//! Host side tooling to run the Kythera tests of the workspace on the actors built in `artifacts/`.
+
+pub mod artifacts;
+pub mod bundle;
+pub mod env;
+pub mod options;
+pub mod report;
+pub mod runner;
+pub mod scenario;
+pub mod size;
+pub mod snapshot;
+pub mod symbolize;
+pub mod trace;
+pub mod validate;
@@ -0,0 +1,252 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Build options of the actors, set in the `[build]` table of the workspace `kythera.toml` and in
+//! the `[package.metadata.kythera.build]` table of each actor, and the variants they are built in.
+//!
+//! Also compiled into the build script, which builds the actors with them.
+
+use std::collections::BTreeMap;
+
+use actor_codegen::metadata::BuildOptions;
+use anyhow::bail;
+use serde::Deserialize;
+
+/// Variant of a target actor built without variant features.
+pub const DEFAULT_VARIANT: &str = "default";
+
+/// The Kind of actors to parse.
+#[derive(Debug)]
+pub enum Kind {
+    Target,
+    Test,
+}
+
+/// The `[build]` table, with options for all actors and for each kind of actors.
+#[derive(Debug, Default, Deserialize)]
+pub struct BuildSection {
+    #[serde(flatten)]
+    all: BuildOptions,
+    #[serde(default)]
+    targets: BuildOptions,
+    #[serde(default)]
+    tests: BuildOptions,
+}
+
+impl BuildSection {
+    /// Resolve the configuration of an actor of the given kind. Its own `options` take precedence
+    /// over the ones of its kind, which take precedence over the ones of all actors.
+    pub fn config(&self, kind: &Kind, options: &BuildOptions) -> BuildConfig {
+        let kind_options = match kind {
+            Kind::Target => &self.targets,
+            Kind::Test => &self.tests,
+        };
+        BuildConfig::new(options.or(&kind_options.or(&self.all)))
+    }
+}
+
+/// Resolved build configuration of an actor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildConfig {
+    pub profile: String,
+    pub features: Vec<String>,
+    pub rustflags: Option<String>,
+    pub locked: bool,
+    pub offline: bool,
+}
+
+impl BuildConfig {
+    /// Resolve `options`, defaulting to a locked build with the `wasm` profile.
+    pub fn new(options: BuildOptions) -> Self {
+        Self {
+            profile: options.profile.unwrap_or_else(|| String::from("wasm")),
+            features: options.features.unwrap_or_default(),
+            rustflags: options.rustflags,
+            locked: options.locked.unwrap_or(true),
+            offline: options.offline.unwrap_or(false),
+        }
+    }
+
+    /// Directory of the profile outputs in the target directory.
+    pub fn profile_dir(&self) -> &str {
+        match self.profile.as_str() {
+            "dev" | "test" => "debug",
+            "bench" => "release",
+            profile => profile,
+        }
+    }
+}
+
+/// Configurations of the builds of the actor `name`, one per variant with the features of the
+/// variant added to `config`, then the default one without variant.
+pub fn variant_builds<'a>(
+    kind: &Kind,
+    name: &str,
+    config: &BuildConfig,
+    variants: &'a BTreeMap<String, Vec<String>>,
+) -> Result<Vec<(Option<&'a str>, BuildConfig)>, anyhow::Error> {
+    if matches!(kind, Kind::Test) && !variants.is_empty() {
+        bail!("Test actor {name} can't declare variants");
+    }
+
+    let mut builds = Vec::new();
+    for (variant, features) in variants {
+        if variant == DEFAULT_VARIANT {
+            bail!("Actor {name} can't name a variant {DEFAULT_VARIANT}");
+        }
+        let mut config = config.clone();
+        config.features.extend(features.iter().cloned());
+        builds.push((Some(variant.as_str()), config));
+    }
+    builds.push((None, config.clone()));
+    Ok(builds)
+}
+
+/// Artifact name of a variant of the target actor `base`, `<Base>-<variant>`.
+pub fn target_name(base: &str, variant: &str) -> String {
+    match variant {
+        DEFAULT_VARIANT => base.to_string(),
+        variant => format!("{base}-{variant}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `[build]` table of a `kythera.toml`.
+    fn section(toml: &str) -> BuildSection {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn profile(profile: &str) -> BuildOptions {
+        BuildOptions {
+            profile: Some(profile.to_string()),
+            ..BuildOptions::default()
+        }
+    }
+
+    #[test]
+    fn defaults_to_locked_wasm_build() {
+        let config = BuildSection::default().config(&Kind::Target, &BuildOptions::default());
+
+        assert_eq!(
+            config,
+            BuildConfig {
+                profile: String::from("wasm"),
+                features: Vec::new(),
+                rustflags: None,
+                locked: true,
+                offline: false,
+            }
+        );
+    }
+
+    #[test]
+    fn kind_options_override_all_actors_options() {
+        let build = section(
+            "profile = \"release\"\nrustflags = \"-C debug-assertions\"\n\
+             [targets]\nprofile = \"wasm-small\"\nfeatures = [\"strict\"]\n\
+             [tests]\nlocked = false\n",
+        );
+
+        let target = build.config(&Kind::Target, &BuildOptions::default());
+        assert_eq!(target.profile, "wasm-small");
+        assert_eq!(target.features, ["strict"]);
+        assert_eq!(target.rustflags.as_deref(), Some("-C debug-assertions"));
+        assert!(target.locked);
+
+        let test = build.config(&Kind::Test, &BuildOptions::default());
+        assert_eq!(test.profile, "release");
+        assert!(test.features.is_empty());
+        assert_eq!(test.rustflags.as_deref(), Some("-C debug-assertions"));
+        assert!(!test.locked);
+    }
+
+    #[test]
+    fn actor_options_override_kind_options() {
+        let build = section("profile = \"release\"\n[targets]\nfeatures = [\"strict\"]\n");
+        let options = BuildOptions {
+            features: Some(Vec::new()),
+            offline: Some(true),
+            ..profile("dev")
+        };
+
+        let config = build.config(&Kind::Target, &options);
+        assert_eq!(config.profile, "dev");
+        assert!(config.features.is_empty());
+        assert!(config.offline);
+    }
+
+    #[test]
+    fn variants_add_their_features() {
+        let config = BuildConfig {
+            features: vec![String::from("base")],
+            ..BuildConfig::new(BuildOptions::default())
+        };
+        let variants = BTreeMap::from([
+            (String::from("strict"), vec![String::from("strict")]),
+            (String::from("lax"), Vec::new()),
+        ]);
+
+        let builds = variant_builds(&Kind::Target, "whitelist", &config, &variants).unwrap();
+        let features: Vec<_> = builds
+            .iter()
+            .map(|(variant, config)| (*variant, config.features.join(",")))
+            .collect();
+        assert_eq!(
+            features,
+            [
+                (Some("lax"), String::from("base")),
+                (Some("strict"), String::from("base,strict")),
+                (None, String::from("base")),
+            ]
+        );
+    }
+
+    #[test]
+    fn actors_without_variants_have_a_single_build() {
+        let config = BuildConfig::new(BuildOptions::default());
+        let variants = BTreeMap::new();
+        let builds = variant_builds(&Kind::Test, "whitelist-test", &config, &variants).unwrap();
+
+        assert_eq!(builds, [(None, config)]);
+    }
+
+    #[test]
+    fn invalid_variants_err() {
+        let config = BuildConfig::new(BuildOptions::default());
+        let variants = |name: &str| BTreeMap::from([(name.to_string(), Vec::new())]);
+        let error = |kind, name, variants| {
+            variant_builds(&kind, name, &config, &variants)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error(Kind::Test, "whitelist-test", variants("strict")),
+            "Test actor whitelist-test can't declare variants"
+        );
+        assert_eq!(
+            error(Kind::Target, "whitelist", variants("default")),
+            "Actor whitelist can't name a variant default"
+        );
+    }
+
+    #[test]
+    fn target_names_of_variants() {
+        assert_eq!(target_name("Whitelist", DEFAULT_VARIANT), "Whitelist");
+        assert_eq!(target_name("Whitelist", "strict"), "Whitelist-strict");
+    }
+
+    #[test]
+    fn profile_dirs() {
+        let dir = |name| BuildConfig::new(profile(name)).profile_dir().to_string();
+
+        assert_eq!(dir("dev"), "debug");
+        assert_eq!(dir("test"), "debug");
+        assert_eq!(dir("bench"), "release");
+        assert_eq!(dir("release"), "release");
+        assert_eq!(dir("wasm"), "wasm");
+    }
+}
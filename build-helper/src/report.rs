@@ -0,0 +1,259 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! JUnit XML and JSON reports of the executed test cases, for CI.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::runner::{TestCase, TestOutcome, TestStatus};
+use crate::scenario::Scenario;
+
+/// Report entry of an executed test case.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestRecord {
+    pub test_actor: String,
+    /// Target actors of the test actor, comma separated.
+    pub target: String,
+    pub method: String,
+    pub status: TestStatus,
+    pub exit_code: Option<u32>,
+    pub message: Option<String>,
+    pub gas_used: Option<u64>,
+    /// Duration of the test case, in seconds.
+    pub duration: f64,
+}
+
+impl TestRecord {
+    pub fn new(case: &TestCase, outcome: &TestOutcome, duration: Duration) -> Self {
+        Self {
+            test_actor: case.pair.test.name().to_string(),
+            target: case
+                .pair
+                .targets
+                .iter()
+                .map(|target| target.actor.name())
+                .collect::<Vec<_>>()
+                .join(","),
+            method: case.method.name().to_string(),
+            status: outcome.status,
+            exit_code: outcome.exit_code.map(|exit_code| exit_code.value()),
+            message: outcome.message.clone(),
+            gas_used: outcome.gas_used,
+            duration: duration.as_secs_f64(),
+        }
+    }
+
+    /// Record of an executed scenario, reported as a method of the `scenario` test actor.
+    pub fn scenario(scenario: &Scenario, outcome: &TestOutcome, duration: Duration) -> Self {
+        Self {
+            test_actor: String::from("scenario"),
+            target: scenario.deployed().join(","),
+            method: scenario.name.clone(),
+            status: outcome.status,
+            exit_code: outcome.exit_code.map(|exit_code| exit_code.value()),
+            message: outcome.message.clone(),
+            gas_used: outcome.gas_used,
+            duration: duration.as_secs_f64(),
+        }
+    }
+}
+
+/// Report of the executed test cases.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    pub tests: Vec<TestRecord>,
+}
+
+impl Report {
+    /// Write the report as JSON to `path`.
+    pub fn write_json(&self, path: &Path) -> Result<(), anyhow::Error> {
+        let json = serde_json::to_string_pretty(self).context("Could not serialize report")?;
+        fs::write(path, json).with_context(|| format!("Could not write report {}", path.display()))
+    }
+
+    /// Write the report as JUnit XML to `path`, with a test suite per test actor.
+    pub fn write_junit(&self, path: &Path) -> Result<(), anyhow::Error> {
+        fs::write(path, self.to_junit())
+            .with_context(|| format!("Could not write report {}", path.display()))
+    }
+
+    fn to_junit(&self) -> String {
+        let mut suites: Vec<(&str, Vec<&TestRecord>)> = Vec::new();
+        for record in &self.tests {
+            match suites
+                .iter_mut()
+                .find(|(name, _)| *name == record.test_actor)
+            {
+                Some((_, records)) => records.push(record),
+                None => suites.push((record.test_actor.as_str(), vec![record])),
+            }
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuites name=\"kythera\" {}>",
+            summary(self.tests.iter())
+        );
+        for (name, records) in suites {
+            let _ = writeln!(
+                xml,
+                "  <testsuite name=\"{}\" {}>",
+                escape(name),
+                summary(records.iter().copied())
+            );
+            for record in records {
+                write_testcase(&mut xml, record);
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// The `tests`, `failures`, `errors` and `time` attributes of a set of records.
+fn summary<'a>(records: impl Iterator<Item = &'a TestRecord>) -> String {
+    let (mut tests, mut failures, mut errors, mut time) = (0, 0, 0, 0.0);
+    for record in records {
+        tests += 1;
+        time += record.duration;
+        match record.status {
+            TestStatus::Passed => {}
+            TestStatus::Failed => failures += 1,
+            TestStatus::Erred => errors += 1,
+        }
+    }
+    format!("tests=\"{tests}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\"")
+}
+
+fn write_testcase(xml: &mut String, record: &TestRecord) {
+    let _ = writeln!(
+        xml,
+        "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">",
+        escape(&record.method),
+        escape(&record.target),
+        record.duration
+    );
+
+    xml.push_str("      <properties>\n");
+    if let Some(exit_code) = record.exit_code {
+        let _ = writeln!(
+            xml,
+            "        <property name=\"exit_code\" value=\"{exit_code}\"/>"
+        );
+    }
+    if let Some(gas_used) = record.gas_used {
+        let _ = writeln!(
+            xml,
+            "        <property name=\"gas_used\" value=\"{gas_used}\"/>"
+        );
+    }
+    xml.push_str("      </properties>\n");
+
+    let element = match record.status {
+        TestStatus::Passed => None,
+        TestStatus::Failed => Some("failure"),
+        TestStatus::Erred => Some("error"),
+    };
+    if let Some(element) = element {
+        let message = record.message.as_deref().unwrap_or_default();
+        let _ = writeln!(
+            xml,
+            "      <{element} message=\"{}\">{}</{element}>",
+            escape(message.lines().next().unwrap_or_default()),
+            escape(message)
+        );
+    }
+
+    xml.push_str("    </testcase>\n");
+}
+
+/// Escape `value` to be used as XML text or attribute.
+///
+/// ANSI escape sequences are stripped, and the other characters XML 1.0 does not allow, such as
+/// NULs or control characters, are replaced with U+FFFD.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\x1b' if chars.peek() == Some(&'[') => {
+                // Skip the parameters of the sequence up to its final byte.
+                chars.next();
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{fffe}' || c == '\u{ffff}' => {
+                escaped.push(char::REPLACEMENT_CHARACTER)
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(status: TestStatus, message: Option<&str>) -> TestRecord {
+        TestRecord {
+            test_actor: String::from("WhitelistTest"),
+            target: String::from("Whitelist"),
+            method: String::from("TestHappyPath"),
+            status,
+            exit_code: message.map(|_| 24),
+            message: message.map(String::from),
+            gas_used: Some(1000),
+            duration: 0.5,
+        }
+    }
+
+    #[test]
+    fn escape_replaces_invalid_characters() {
+        assert_eq!(escape("a < b & \"c\""), "a &lt; b &amp; &quot;c&quot;");
+        assert_eq!(escape("\x1b[31mred\x1b[0m"), "red");
+        assert_eq!(escape("nul\0bell\x07"), "nul\u{fffd}bell\u{fffd}");
+        assert_eq!(escape("tab\tline\n"), "tab\tline\n");
+    }
+
+    #[test]
+    fn junit_of_failure_with_control_characters() {
+        let report = Report {
+            tests: vec![
+                record(TestStatus::Passed, None),
+                record(
+                    TestStatus::Failed,
+                    Some("\x1b[1massert_eq\x1b[0m failed\n  left: \0<1>\n  at actor.rs:12"),
+                ),
+            ],
+        };
+
+        let xml = report.to_junit();
+        assert!(!xml
+            .chars()
+            .any(|c| c < ' ' && !matches!(c, '\t' | '\n' | '\r')));
+        assert!(xml.contains(
+            "<testsuite name=\"WhitelistTest\" tests=\"2\" failures=\"1\" errors=\"0\" time=\"1.000\">"
+        ));
+        assert!(xml.contains(
+            "<failure message=\"assert_eq failed\">assert_eq failed\n  left: \u{fffd}&lt;1&gt;\n  at actor.rs:12</failure>"
+        ));
+    }
+}
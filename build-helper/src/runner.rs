@@ -0,0 +1,379 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Execution of the methods of the test actors as individual test cases.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use actor_codegen::metadata::ExpectedFailure;
+use actor_utils::targets::Targets;
+use anyhow::Context;
+use convert_case::{Case, Casing};
+use fvm::executor::ApplyRet;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::error::ExitCode;
+use fvm_shared::state::StateTreeVersion;
+use fvm_shared::version::NetworkVersion;
+use fvm_shared::ActorID;
+use kythera_lib::{Method, Tester, WasmActor};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::artifacts::ActorPair;
+use crate::env::TestEnv;
+use crate::symbolize::{symbolize, Symbolizer};
+use crate::trace::{CallTrace, MethodNames};
+
+/// Create a tester with the builtin actors deployed.
+pub(crate) fn tester() -> Tester {
+    Tester::new(
+        NetworkVersion::V18,
+        StateTreeVersion::V5,
+        actors_v10::BUNDLE_CAR,
+    )
+}
+
+/// Status of an executed test case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    /// The test method behaved as expected.
+    Passed,
+    /// The test method did not behave as expected.
+    Failed,
+    /// The test method could not be executed.
+    Erred,
+}
+
+/// Outcome of an executed test case.
+#[derive(Debug, Clone)]
+pub struct TestOutcome {
+    pub status: TestStatus,
+    /// Exit code of the test method, if it was executed.
+    pub exit_code: Option<ExitCode>,
+    /// Abort message of the test method or error of its execution.
+    pub message: Option<String>,
+    /// Gas used by the test method, if it was executed.
+    pub gas_used: Option<u64>,
+    /// Calls made by the test method, if it was executed.
+    pub trace: Vec<CallTrace>,
+}
+
+impl TestOutcome {
+    /// Outcome of an executed test method, `TestFail` methods being expected to fail.
+    fn new(method: &Method, apply_ret: &ApplyRet) -> Self {
+        let success = apply_ret.msg_receipt.exit_code.is_success();
+        let status = if success != method.name().starts_with("TestFail") {
+            TestStatus::Passed
+        } else {
+            TestStatus::Failed
+        };
+
+        Self {
+            status,
+            exit_code: Some(apply_ret.msg_receipt.exit_code),
+            message: apply_ret.failure_info.as_ref().map(|info| info.to_string()),
+            gas_used: Some(apply_ret.msg_receipt.gas_used),
+            trace: CallTrace::from_events(&apply_ret.exec_trace),
+        }
+    }
+
+    /// Outcome of a test case that could not be run.
+    pub fn erred(err: &anyhow::Error) -> Self {
+        Self {
+            status: TestStatus::Erred,
+            exit_code: None,
+            message: Some(format!("{err:#}")),
+            gas_used: None,
+            trace: Vec::new(),
+        }
+    }
+
+    /// Check that an executed method failed with the `expected` exit code and abort message.
+    fn expect_failure(mut self, expected: &ExpectedFailure) -> Result<Self, anyhow::Error> {
+        if self.status == TestStatus::Erred {
+            return Ok(self);
+        }
+
+        let pattern = expected
+            .message
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .context("Invalid expected failure message pattern")?;
+        let exit_code_matches = self.exit_code.map(ExitCode::value) == Some(expected.exit_code);
+        let message_matches = match &pattern {
+            Some(pattern) => self
+                .message
+                .as_deref()
+                .map_or(false, |message| pattern.is_match(message)),
+            None => true,
+        };
+
+        if exit_code_matches && message_matches {
+            self.status = TestStatus::Passed;
+        } else {
+            self.status = TestStatus::Failed;
+            self.message = Some(format!(
+                "expected failure with exit code {}{}, got: {}",
+                expected.exit_code,
+                pattern
+                    .map(|pattern| format!(" and message matching `{pattern}`"))
+                    .unwrap_or_default(),
+                self.message.as_deref().unwrap_or("no message")
+            ));
+        }
+        Ok(self)
+    }
+
+    /// Symbolicate the wasm backtraces of the message with the debug modules of the `deployed`
+    /// actors, skipping those whose code differs from the deployed module.
+    fn symbolize(
+        &mut self,
+        debug: &[(String, Vec<u8>)],
+        deployed: &[(&WasmActor, ActorID)],
+    ) -> Result<(), anyhow::Error> {
+        if let Some(message) = &self.message {
+            let mut symbolizers = Vec::new();
+            for (actor, id) in deployed {
+                let wasm = debug.iter().find(|(name, _)| name == actor.name());
+                if let Some((name, wasm)) = wasm {
+                    symbolizers.extend(
+                        Symbolizer::new(name, *id, wasm, actor.code())
+                            .context("Could not load debug symbols")?,
+                    );
+                }
+            }
+            self.message = Some(symbolize(message, &symbolizers).into_owned());
+        }
+        Ok(())
+    }
+
+    pub fn passed(&self) -> bool {
+        self.status == TestStatus::Passed
+    }
+}
+
+impl fmt::Display for TestOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.status)?;
+        if let Some(exit_code) = self.exit_code {
+            write!(f, " with exit code {}", exit_code.value())?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A single test method of a test actor.
+#[derive(Clone)]
+pub struct TestCase {
+    pub pair: ActorPair,
+    pub method: Method,
+}
+
+impl TestCase {
+    /// Name of the case, `<target>::<method>`, e.g. `whitelist::TestHappyPath`.
+    pub fn name(&self) -> String {
+        format!(
+            "{}::{}",
+            self.pair.name.to_case(Case::Snake),
+            self.method.name()
+        )
+    }
+
+    /// Names of the methods of the target and test actors, to resolve them in traces.
+    pub fn method_names(&self) -> MethodNames {
+        MethodNames::new(
+            self.pair
+                .targets
+                .iter()
+                .map(|target| target.actor.abi())
+                .chain([self.pair.test.abi()]),
+        )
+    }
+
+    /// Run the test method against freshly deployed targets.
+    ///
+    /// The targets are constructed with the constructor params of the test actor metadata, and the
+    /// test actor is deployed and set up for this method only, so that cases are isolated from
+    /// each other and can be filtered. Methods with an expected failure declared in the test actor
+    /// metadata only pass if they abort with the expected exit code and message.
+    pub fn run(&self) -> Result<TestOutcome, anyhow::Error> {
+        let mut env = TestEnv::new()?;
+        let mut targets = BTreeMap::new();
+        let mut deployed = Vec::new();
+        for target in &self.pair.targets {
+            let id = env
+                .deploy_actor(
+                    &target.actor,
+                    target.constructor_params.clone().map(RawBytes::new),
+                )
+                .with_context(|| format!("Could not deploy target {}", target.actor.name()))?;
+            targets.insert(target.name.clone(), id);
+            deployed.push((&target.actor, id));
+        }
+        let test = env.deploy_actor(&self.pair.test, None)?;
+        deployed.push((&self.pair.test, test));
+
+        // Test methods get the IDs of the targets by name when the test actor declares them, and
+        // the ID of its single target otherwise.
+        let params = if self.pair.named_targets {
+            RawBytes::serialize(Targets::new(targets))
+        } else {
+            RawBytes::serialize(targets.into_values().next().context("No target to test")?)
+        }
+        .context("Could not encode target IDs")?;
+        if let Some(set_up) = &self.pair.test.abi().set_up {
+            let ret = env.apply(test, set_up.number(), Some(params.clone()))?;
+            if !ret.msg_receipt.exit_code.is_success() {
+                let mut outcome = TestOutcome::new(set_up, &ret);
+                outcome.message = Some(format!(
+                    "SetUp failed: {}",
+                    outcome.message.as_deref().unwrap_or("no message")
+                ));
+                outcome.symbolize(&self.pair.debug, &deployed)?;
+                return Ok(outcome);
+            }
+        }
+
+        let ret = env
+            .apply(test, self.method.number(), Some(params))
+            .with_context(|| format!("Could not run {}", self.name()))?;
+        let mut outcome = TestOutcome::new(&self.method, &ret);
+        if let Some(expected) = self.pair.metadata.expect.get(self.method.name()) {
+            outcome = outcome.expect_failure(expected)?;
+        }
+        if !outcome.passed() {
+            outcome.symbolize(&self.pair.debug, &deployed)?;
+        }
+        Ok(outcome)
+    }
+}
+
+/// List the test cases of the given actor pairs, one per test method.
+pub fn test_cases(pairs: &[ActorPair]) -> Vec<TestCase> {
+    pairs
+        .iter()
+        .flat_map(|pair| {
+            pair.test.abi().methods.iter().map(|method| TestCase {
+                pair: pair.clone(),
+                method: method.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Outcome of a `TestFail` method exiting with `exit_code` and `message`.
+    fn outcome(exit_code: ExitCode, message: Option<&str>) -> TestOutcome {
+        TestOutcome {
+            status: if exit_code.is_success() {
+                TestStatus::Failed
+            } else {
+                TestStatus::Passed
+            },
+            exit_code: Some(exit_code),
+            message: message.map(str::to_string),
+            gas_used: Some(1000),
+            trace: Vec::new(),
+        }
+    }
+
+    fn expected(exit_code: u32, message: Option<&str>) -> ExpectedFailure {
+        ExpectedFailure {
+            exit_code,
+            message: message.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn matching_exit_code_passes() {
+        let outcome = outcome(ExitCode::USR_FORBIDDEN, Some("Only administrator"))
+            .expect_failure(&expected(18, None))
+            .unwrap();
+
+        assert_eq!(outcome.status, TestStatus::Passed);
+        assert_eq!(outcome.message.as_deref(), Some("Only administrator"));
+    }
+
+    #[test]
+    fn matching_exit_code_and_message_passes() {
+        let outcome = outcome(ExitCode::USR_FORBIDDEN, Some("Only administrator can set"))
+            .expect_failure(&expected(18, Some("^Only admin")))
+            .unwrap();
+
+        assert_eq!(outcome.status, TestStatus::Passed);
+    }
+
+    #[test]
+    fn other_exit_code_fails() {
+        let outcome = outcome(ExitCode::USR_ILLEGAL_STATE, Some("actor has no state"))
+            .expect_failure(&expected(18, None))
+            .unwrap();
+
+        assert_eq!(outcome.status, TestStatus::Failed);
+        assert_eq!(
+            outcome.message.as_deref(),
+            Some("expected failure with exit code 18, got: actor has no state")
+        );
+    }
+
+    #[test]
+    fn other_message_fails() {
+        let outcome = outcome(ExitCode::USR_FORBIDDEN, Some("Not whitelisted"))
+            .expect_failure(&expected(18, Some("administrator")))
+            .unwrap();
+
+        assert_eq!(outcome.status, TestStatus::Failed);
+        assert_eq!(
+            outcome.message.as_deref(),
+            Some(
+                "expected failure with exit code 18 and message matching `administrator`, got: \
+                 Not whitelisted"
+            )
+        );
+    }
+
+    #[test]
+    fn success_fails() {
+        let outcome = outcome(ExitCode::OK, None)
+            .expect_failure(&expected(18, None))
+            .unwrap();
+
+        assert_eq!(outcome.status, TestStatus::Failed);
+        assert_eq!(
+            outcome.message.as_deref(),
+            Some("expected failure with exit code 18, got: no message")
+        );
+    }
+
+    #[test]
+    fn erred_outcome_is_kept() {
+        let err = anyhow::anyhow!("Could not deploy Whitelist");
+        let outcome = TestOutcome::erred(&err)
+            .expect_failure(&expected(18, None))
+            .unwrap();
+
+        assert_eq!(outcome.status, TestStatus::Erred);
+        assert_eq!(
+            outcome.message.as_deref(),
+            Some("Could not deploy Whitelist")
+        );
+    }
+
+    #[test]
+    fn invalid_message_pattern_errs() {
+        let err = outcome(ExitCode::USR_FORBIDDEN, None)
+            .expect_failure(&expected(18, Some("(")))
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid expected failure message pattern");
+    }
+}
@@ -0,0 +1,532 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Declarative scenario tests, run against the actors in `artifacts/` without a test actor.
+//!
+//! A scenario is a TOML file of the workspace `scenarios` dir, listing steps run in order:
+//!
+//! ```toml
+//! [[step]]
+//! deploy = "Whitelist"
+//! params = '["@alice"]'
+//!
+//! [[step]]
+//! call = "whitelist"
+//! method = "SetWhitelist"
+//! from = "alice"
+//! params = '["@bob", true]'
+//!
+//! [[step]]
+//! call = "whitelist"
+//! method = "IsWhitelisted"
+//! params = '"@bob"'
+//! returns = 'true'
+//! ```
+//!
+//! Params and return values are JSON in the shape of their DAG-CBOR encoding, tuple structs being
+//! arrays. Strings of the form `@<name>` are the ID address of a deployed actor or named account,
+//! or a literal address such as `@f01234`. Numbers are integers fitting in a `u64` or `i64`: byte
+//! strings, big integers such as token amounts and floats have no JSON form, flows passing them are
+//! written as test actors instead. The methods of a call are looked up in the Abi of the called
+//! actor.
+//!
+//! Deployments must succeed, and can't expect an exit code or return value. Calls are sent by the
+//! `from` named account rather than through the `Prank` and `Trick` cheatcodes, which only apply to
+//! messages sent by actors.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use actor_utils::cheatcodes::CHEATCODES_ACTOR_ID;
+use anyhow::{bail, Context};
+use convert_case::{Case, Casing};
+use fvm::executor::ApplyRet;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::error::ExitCode;
+use fvm_shared::ActorID;
+use kythera_lib::{Method, WasmActor};
+use serde::{ser, Deserialize, Serialize};
+
+use crate::artifacts::load_actor;
+use crate::env::TestEnv;
+use crate::runner::{TestOutcome, TestStatus};
+use crate::trace::{self, CallTrace, MethodNames, CHEATCODES};
+
+/// The `scenarios` directory of the workspace.
+pub fn scenarios_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../scenarios")
+}
+
+/// A scenario, named after its file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(skip)]
+    pub name: String,
+    #[serde(default, rename = "step")]
+    pub steps: Vec<Step>,
+}
+
+/// A step of a scenario: a deployment, a call or a cheatcode.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Step {
+    /// Artifact name of the actor to deploy, calling its constructor with the params.
+    pub deploy: Option<String>,
+    /// Name the deployed actor is called with, its snake case artifact name by default.
+    #[serde(rename = "as")]
+    pub alias: Option<String>,
+    /// Name of the deployed actor to call.
+    pub call: Option<String>,
+    /// Method of the called actor.
+    pub method: Option<String>,
+    /// Cheatcode to apply.
+    pub cheatcode: Option<String>,
+    /// Named account sending the call, the environment sender by default.
+    pub from: Option<String>,
+    /// JSON params of the step.
+    pub params: Option<String>,
+    /// Exit code the call or cheatcode must exit with, 0 by default.
+    #[serde(default)]
+    pub exit_code: u32,
+    /// JSON value the call must return.
+    pub returns: Option<String>,
+}
+
+impl Step {
+    /// Check the step sets one of deploy, call or cheatcode, along with the fields that apply to it.
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        match (&self.deploy, &self.call, &self.cheatcode) {
+            (Some(_), None, None) => {
+                if self.exit_code != 0 || self.returns.is_some() {
+                    bail!("Deployments must succeed, they can't set exit-code or returns");
+                }
+                if self.method.is_some() || self.from.is_some() {
+                    bail!("Deployments can't set method or from");
+                }
+            }
+            (None, Some(_), None) => {
+                if self.method.is_none() {
+                    bail!("Calls must set a method");
+                }
+                if self.alias.is_some() {
+                    bail!("Only deployments can set as");
+                }
+            }
+            (None, None, Some(cheatcode)) => {
+                if !CHEATCODES.contains(&cheatcode.as_str()) {
+                    bail!("Unknown cheatcode {cheatcode}");
+                }
+                if cheatcode == "Prank" || cheatcode == "Trick" {
+                    bail!("{cheatcode} has no effect on the next step, send it `from` an account");
+                }
+                if self.method.is_some() || self.alias.is_some() {
+                    bail!("Cheatcodes can't set method or as");
+                }
+            }
+            _ => bail!("Steps must set one of deploy, call or cheatcode"),
+        }
+        Ok(())
+    }
+
+    /// Description of the step in failures.
+    fn describe(&self) -> String {
+        match (&self.deploy, &self.call, &self.cheatcode) {
+            (Some(actor), _, _) => format!("deploy {actor}"),
+            (_, Some(actor), _) => format!(
+                "call {actor}::{}",
+                self.method.as_deref().unwrap_or_default()
+            ),
+            (_, _, Some(cheatcode)) => format!("cheatcode {cheatcode}"),
+            _ => String::from("empty step"),
+        }
+    }
+}
+
+/// Load the scenarios of `dir`, sorted by name. Empty if it does not exist.
+pub fn load_scenarios(dir: &Path) -> Result<Vec<Scenario>, anyhow::Error> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("Could not read scenarios dir {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "toml"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Could not read scenario {}", path.display()))?;
+            let mut scenario: Scenario = toml::from_str(&content)
+                .with_context(|| format!("Invalid scenario {}", path.display()))?;
+            scenario.name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .with_context(|| format!("Invalid scenario file name {}", path.display()))?
+                .to_string();
+            for (index, step) in scenario.steps.iter().enumerate() {
+                step.validate().with_context(|| {
+                    format!(
+                        "Invalid step {} ({}) of scenario {}",
+                        index + 1,
+                        step.describe(),
+                        path.display()
+                    )
+                })?;
+            }
+            Ok(scenario)
+        })
+        .collect()
+}
+
+/// State of a running scenario.
+struct Run {
+    env: TestEnv,
+    /// Deployed actors, by name.
+    actors: BTreeMap<String, (ActorID, WasmActor)>,
+    gas_used: u64,
+    trace: Vec<CallTrace>,
+}
+
+impl Run {
+    /// Resolve the `@<name>` address of a deployed actor, named account or literal address.
+    fn address(&self, name: &str) -> Result<Address, anyhow::Error> {
+        if let Some((id, _)) = self.actors.get(name) {
+            return Ok(Address::new_id(*id));
+        }
+        if let Ok(account) = self.env.account(name) {
+            return Ok(Address::new_id(account.id));
+        }
+        Address::from_str(name).with_context(|| format!("Unknown actor or account @{name}"))
+    }
+
+    /// Encode JSON params as DAG-CBOR.
+    fn encode(&self, json: &str) -> Result<RawBytes, anyhow::Error> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).with_context(|| format!("Invalid JSON {json}"))?;
+        let value = Value::from_json(value, &|name| self.address(name))?;
+        RawBytes::serialize(value).context("Could not encode params")
+    }
+
+    /// Send a message, recording its gas and trace.
+    fn apply(
+        &mut self,
+        from: Option<&str>,
+        to: ActorID,
+        method: &Method,
+        params: Option<RawBytes>,
+    ) -> Result<ApplyRet, anyhow::Error> {
+        let from = match from {
+            Some(from) => self.env.account(from)?.id,
+            None => self.env.sender(),
+        };
+        let ret = self
+            .env
+            .apply_message(from, Address::new_id(to), method.number(), params)?;
+        self.gas_used += ret.msg_receipt.gas_used;
+        self.trace.extend(CallTrace::from_events(&ret.exec_trace));
+        Ok(ret)
+    }
+
+    /// Run a step, returning why it did not behave as expected.
+    fn step(&mut self, step: &Step) -> Result<Option<String>, anyhow::Error> {
+        let params = step
+            .params
+            .as_deref()
+            .map(|params| self.encode(params))
+            .transpose()?;
+
+        let ret = match (&step.deploy, &step.call, &step.cheatcode) {
+            (Some(artifact), None, None) => {
+                let actor = load_actor(self.env.artifacts_dir(), artifact)?;
+                let id = self.env.deploy_actor(&actor, params)?;
+                let name = step
+                    .alias
+                    .clone()
+                    .unwrap_or_else(|| artifact.to_case(Case::Snake));
+                self.actors.insert(name, (id, actor));
+                return Ok(None);
+            }
+            (None, Some(name), None) => {
+                let (id, actor) = self
+                    .actors
+                    .get(name)
+                    .with_context(|| format!("No deployed actor {name}"))?;
+                let method = step.method.as_deref().context("Calls must set a method")?;
+                let method = actor
+                    .abi()
+                    .methods
+                    .iter()
+                    .find(|m| m.name() == method)
+                    .cloned()
+                    .with_context(|| format!("{} has no method {method}", actor.name()))?;
+                let id = *id;
+                self.apply(step.from.as_deref(), id, &method, params)?
+            }
+            (None, None, Some(cheatcode)) => {
+                let method = Method::new_from_name(cheatcode)
+                    .with_context(|| format!("Invalid cheatcode name {cheatcode}"))?;
+                self.apply(step.from.as_deref(), CHEATCODES_ACTOR_ID, &method, params)?
+            }
+            _ => bail!("Steps must set one of deploy, call or cheatcode"),
+        };
+
+        let exit_code = ret.msg_receipt.exit_code;
+        if exit_code != ExitCode::new(step.exit_code) {
+            return Ok(Some(format!(
+                "expected exit code {}, got {}{}",
+                step.exit_code,
+                exit_code.value(),
+                ret.failure_info
+                    .map(|info| format!(": {info}"))
+                    .unwrap_or_default()
+            )));
+        }
+        if let Some(returns) = &step.returns {
+            let expected = self.encode(returns)?;
+            let actual = ret.msg_receipt.return_data;
+            // Methods returning nothing match a `null` return value.
+            let matches =
+                expected == actual || (actual.is_empty() && expected.bytes() == [0xf6].as_slice());
+            if !matches {
+                return Ok(Some(format!(
+                    "expected return value {returns}, got {}",
+                    trace::decode(actual.bytes())
+                )));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Scenario {
+    /// Name of the test case of the scenario, `scenario::<name>`.
+    pub fn case_name(&self) -> String {
+        format!("scenario::{}", self.name)
+    }
+
+    /// Artifact names of the actors the scenario deploys.
+    pub fn deployed(&self) -> Vec<&str> {
+        self.steps
+            .iter()
+            .filter_map(|step| step.deploy.as_deref())
+            .collect()
+    }
+
+    /// Names of the methods of the deployed actors, to resolve them in traces.
+    pub fn method_names(&self, artifacts_dir: &Path) -> MethodNames {
+        let actors = self
+            .deployed()
+            .into_iter()
+            .filter_map(|name| load_actor(artifacts_dir, name).ok())
+            .collect::<Vec<_>>();
+        MethodNames::new(actors.iter().map(WasmActor::abi))
+    }
+
+    /// Run the steps of the scenario in a fresh environment, failing at the first one that does
+    /// not exit or return as expected.
+    pub fn run(&self) -> Result<TestOutcome, anyhow::Error> {
+        let mut run = Run {
+            env: TestEnv::new()?,
+            actors: BTreeMap::new(),
+            gas_used: 0,
+            trace: Vec::new(),
+        };
+
+        let mut outcome = TestOutcome {
+            status: TestStatus::Passed,
+            exit_code: None,
+            message: None,
+            gas_used: None,
+            trace: Vec::new(),
+        };
+        for (index, step) in self.steps.iter().enumerate() {
+            let failure = run
+                .step(step)
+                .with_context(|| format!("Step {} ({})", index + 1, step.describe()))?;
+            if let Some(failure) = failure {
+                outcome.status = TestStatus::Failed;
+                outcome.message = Some(format!(
+                    "step {} ({}): {failure}",
+                    index + 1,
+                    step.describe()
+                ));
+                break;
+            }
+        }
+
+        outcome.gas_used = Some(run.gas_used);
+        outcome.trace = run.trace;
+        Ok(outcome)
+    }
+}
+
+/// A JSON value of a scenario, with its `@<name>` strings resolved to addresses.
+///
+/// Only integers fitting in a `u64` or `i64` are supported, there is no byte string or big integer.
+#[derive(Debug)]
+enum Value {
+    Null,
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    String(String),
+    Address(Address),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    fn from_json(
+        value: serde_json::Value,
+        address: &dyn Fn(&str) -> Result<Address, anyhow::Error>,
+    ) -> Result<Self, anyhow::Error> {
+        Ok(match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+                (Some(n), _) => Value::Unsigned(n),
+                (None, Some(n)) => Value::Signed(n),
+                _ => bail!("Unsupported number {n}, only integers are"),
+            },
+            serde_json::Value::String(s) => match s.strip_prefix('@') {
+                Some(name) => Value::Address(address(name)?),
+                None => Value::String(s),
+            },
+            serde_json::Value::Array(values) => Value::List(
+                values
+                    .into_iter()
+                    .map(|value| Value::from_json(value, address))
+                    .collect::<Result<_, _>>()?,
+            ),
+            serde_json::Value::Object(entries) => Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, Value::from_json(value, address)?)))
+                    .collect::<Result<_, anyhow::Error>>()?,
+            ),
+        })
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Unsigned(n) => serializer.serialize_u64(*n),
+            Value::Signed(n) => serializer.serialize_i64(*n),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Address(address) => address.serialize(serializer),
+            Value::List(values) => serializer.collect_seq(values),
+            Value::Map(entries) => serializer.collect_map(entries),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// DAG-CBOR encoding of `json`, with `@alice` at f0101.
+    fn encode(json: &str) -> Result<Vec<u8>, anyhow::Error> {
+        let value = Value::from_json(serde_json::from_str(json).unwrap(), &|name| match name {
+            "alice" => Ok(Address::new_id(101)),
+            _ => bail!("Unknown actor or account @{name}"),
+        })?;
+        Ok(fvm_ipld_encoding::to_vec(&value).unwrap())
+    }
+
+    #[test]
+    fn json_values_encode_as_dag_cbor() {
+        assert_eq!(encode("null").unwrap(), [0xf6]);
+        assert_eq!(encode("[true, 1, -1]").unwrap(), [0x83, 0xf5, 0x01, 0x20]);
+        assert_eq!(encode(r#""bob""#).unwrap(), [0x63, b'b', b'o', b'b']);
+        assert_eq!(encode(r#"{"a": 1}"#).unwrap(), [0xa1, 0x61, b'a', 0x01]);
+    }
+
+    #[test]
+    fn names_resolve_to_addresses() {
+        assert_eq!(
+            encode(r#"["@alice", true]"#).unwrap(),
+            [0x82, 0x42, 0x00, 0x65, 0xf5]
+        );
+        assert_eq!(
+            encode(r#""@carol""#).unwrap_err().to_string(),
+            "Unknown actor or account @carol"
+        );
+    }
+
+    #[test]
+    fn floats_are_rejected() {
+        assert_eq!(
+            encode("1.5").unwrap_err().to_string(),
+            "Unsupported number 1.5, only integers are"
+        );
+    }
+
+    #[test]
+    fn load_scenarios_sorted_by_name() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("b.toml"),
+            "[[step]]\ndeploy = \"Whitelist\"\nas = \"list\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("a.toml"),
+            "[[step]]\ncall = \"list\"\nmethod = \"IsWhitelisted\"\nreturns = 'true'\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.md"), "not a scenario").unwrap();
+
+        let scenarios = load_scenarios(dir.path()).unwrap();
+
+        let names: Vec<_> = scenarios.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(scenarios[0].case_name(), "scenario::a");
+        assert_eq!(scenarios[1].deployed(), ["Whitelist"]);
+    }
+
+    #[test]
+    fn load_scenarios_rejects_invalid_steps() {
+        for (name, step, error) in [
+            (
+                "deploy-returns",
+                "deploy = \"Whitelist\"\nreturns = 'true'",
+                "Deployments must succeed, they can't set exit-code or returns",
+            ),
+            (
+                "deploy-exit-code",
+                "deploy = \"Whitelist\"\nexit-code = 16",
+                "Deployments must succeed, they can't set exit-code or returns",
+            ),
+            (
+                "prank",
+                "cheatcode = \"Prank\"\nparams = '\"@alice\"'",
+                "Prank has no effect on the next step, send it `from` an account",
+            ),
+            (
+                "empty",
+                "params = 'null'",
+                "Steps must set one of deploy, call or cheatcode",
+            ),
+        ] {
+            let dir = TempDir::new().unwrap();
+            let scenario = format!("[[step]]\n{step}\n");
+            fs::write(dir.path().join("scenario.toml"), scenario).unwrap();
+
+            let err = load_scenarios(dir.path()).unwrap_err();
+            assert_eq!(err.root_cause().to_string(), error, "{name}");
+        }
+    }
+}
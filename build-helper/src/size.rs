@@ -0,0 +1,218 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Size breakdown of the built wasm modules.
+//!
+//! Also compiled into the build script, which reports the size of the actors before publishing
+//! them.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+use wasmparser::{Name, NameSectionReader, Parser, Payload, TypeRef};
+
+/// Number of the largest functions and data segments listed in a size report.
+const TOP_ENTRIES: usize = 10;
+
+/// Size of a wasm module, by section and by function and data segment.
+pub struct SizeReport {
+    pub total: usize,
+    pub sections: Vec<(String, usize)>,
+    /// Functions and data segments, largest first.
+    pub entries: Vec<(String, usize)>,
+}
+
+/// Name of a known section id.
+fn section_name(id: u8) -> &'static str {
+    match id {
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "datacount",
+        _ => "unknown",
+    }
+}
+
+/// The bytes of the code section of `wasm`, empty if it has none.
+pub fn code_section(wasm: &[u8]) -> Result<&[u8], anyhow::Error> {
+    Ok(&wasm[code_range(wasm)?])
+}
+
+/// The range of the code section in `wasm`, empty if it has none.
+pub fn code_range(wasm: &[u8]) -> Result<Range<usize>, anyhow::Error> {
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::CodeSectionStart { range, .. } = payload? {
+            return Ok(range);
+        }
+    }
+    Ok(0..0)
+}
+
+/// Function names of the name section of `wasm`, by function index.
+fn function_names(wasm: &[u8]) -> Result<HashMap<u32, String>, anyhow::Error> {
+    let mut names = HashMap::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::CustomSection(reader) if reader.name() == "name" => {
+                for name in NameSectionReader::new(reader.data(), reader.data_offset()) {
+                    if let Name::Function(map) = name? {
+                        for naming in map {
+                            let naming = naming?;
+                            names.insert(naming.index, naming.name.to_string());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(names)
+}
+
+/// Break down the size of `wasm`.
+///
+/// Stripped modules have no function names, which are then read from `debug`, the same module
+/// built with its name section, as long as it has the same code.
+pub fn analyze(wasm: &[u8], debug: Option<&[u8]>) -> Result<SizeReport, anyhow::Error> {
+    let mut sections = Vec::new();
+    let mut imported_functions = 0;
+    let mut bodies = Vec::new();
+    let mut data = Vec::new();
+    let mut names = match debug {
+        Some(debug) if code_section(debug)? == code_section(wasm)? => function_names(debug)?,
+        _ => function_names(wasm)?,
+    };
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = payload?;
+        if let Some((id, range)) = payload.as_section() {
+            let name = match &payload {
+                Payload::CustomSection(reader) => format!("custom:{}", reader.name()),
+                _ => section_name(id).to_string(),
+            };
+            sections.push((name, range.len()));
+        }
+
+        match payload {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    if let TypeRef::Func(_) = import?.ty {
+                        imported_functions += 1;
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => bodies.push(body.range().len()),
+            Payload::DataSection(reader) => {
+                for segment in reader {
+                    data.push(segment?.data.len());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut entries = bodies
+        .into_iter()
+        .enumerate()
+        .map(|(i, size)| {
+            let index = imported_functions + i as u32;
+            let name = names
+                .remove(&index)
+                .unwrap_or_else(|| format!("func[{index}]"));
+            (name, size)
+        })
+        .chain(
+            data.into_iter()
+                .enumerate()
+                .map(|(i, size)| (format!("data[{i}]"), size)),
+        )
+        .collect::<Vec<_>>();
+    entries.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    Ok(SizeReport {
+        total: wasm.len(),
+        sections,
+        entries,
+    })
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} bytes", self.total)?;
+        for (name, size) in &self.sections {
+            writeln!(f, "  section {name}: {size} bytes")?;
+        }
+        for (name, size) in self.entries.iter().take(TOP_ENTRIES) {
+            writeln!(f, "  {name}: {size} bytes")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A module with an imported function, whose functions are named when `named`.
+    fn module(named: bool) -> Vec<u8> {
+        let name = |name: &str| {
+            if named {
+                format!("${name}")
+            } else {
+                String::new()
+            }
+        };
+        wat::parse_str(format!(
+            r#"(module
+                (import "vm" "exit" (func (param i32)))
+                (memory 1)
+                (func {} (param i32) (result i32) local.get 0 i32.const 1 i32.add i32.const 2 i32.mul)
+                (func {} (export "invoke") (param i32) (result i32) local.get 0 call 1)
+                (data (i32.const 0) "abc"))"#,
+            name("double_next"),
+            name("invoke"),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn stripped_module_has_indices() {
+        let report = analyze(&module(false), None).unwrap();
+
+        assert_eq!(report.entries[0].0, "func[1]");
+        assert!(report.entries.iter().any(|(name, _)| name == "func[2]"));
+        assert!(report.entries.contains(&(String::from("data[0]"), 3)));
+    }
+
+    #[test]
+    fn names_from_debug_module() {
+        let report = analyze(&module(false), Some(&module(true))).unwrap();
+
+        assert_eq!(report.entries[0].0, "double_next");
+        assert!(report.entries.iter().any(|(name, _)| name == "invoke"));
+        assert!(report
+            .sections
+            .iter()
+            .all(|(name, _)| name != "custom:name"));
+    }
+
+    #[test]
+    fn debug_module_with_other_code_is_ignored() {
+        let other = wat::parse_str(
+            r#"(module (func $invoke (export "invoke") (param i32) (result i32) local.get 0))"#,
+        )
+        .unwrap();
+        let report = analyze(&module(false), Some(&other)).unwrap();
+
+        assert_eq!(report.entries[0].0, "func[1]");
+    }
+}
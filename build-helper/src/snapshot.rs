@@ -0,0 +1,257 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Gas snapshots of the test cases, committed to catch gas regressions.
+//!
+//! The snapshot file holds a line per test case, e.g. `whitelist::TestHappyPath (gas: 1234)`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+
+/// The gas snapshot file of the workspace.
+pub fn snapshot_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../.gas-snapshot")
+}
+
+/// What to do with the gas snapshot when running the tests, set by `KYTHERA_GAS_SNAPSHOT`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotMode {
+    /// Record the gas used by the executed cases in the snapshot, dropping the cases that no longer
+    /// exist.
+    Write,
+    /// Fail the cases whose gas moved by more than `tolerance` percent from the snapshot, or that
+    /// are missing from it.
+    Check { tolerance: f64 },
+}
+
+impl SnapshotMode {
+    /// Read the mode from `KYTHERA_GAS_SNAPSHOT` (`write` or `check`), and the tolerance of the
+    /// check from `KYTHERA_GAS_TOLERANCE`, in percent, defaulting to 0.
+    pub fn from_env() -> Result<Option<Self>, anyhow::Error> {
+        let mode = match std::env::var("KYTHERA_GAS_SNAPSHOT") {
+            Ok(mode) => mode,
+            Err(_) => return Ok(None),
+        };
+
+        match mode.as_str() {
+            "write" => Ok(Some(Self::Write)),
+            "check" => {
+                let tolerance = match std::env::var("KYTHERA_GAS_TOLERANCE") {
+                    Ok(tolerance) => parse_tolerance(&tolerance)?,
+                    Err(_) => 0.0,
+                };
+                Ok(Some(Self::Check { tolerance }))
+            }
+            mode => bail!("Invalid gas snapshot mode {mode}, expected write or check"),
+        }
+    }
+}
+
+/// Parse a gas tolerance, a finite and non-negative percentage.
+fn parse_tolerance(tolerance: &str) -> Result<f64, anyhow::Error> {
+    let parsed: f64 = tolerance
+        .parse()
+        .with_context(|| format!("Invalid gas tolerance {tolerance}"))?;
+    if !parsed.is_finite() || parsed < 0.0 {
+        bail!("Invalid gas tolerance {tolerance}, expected a non-negative percentage");
+    }
+    Ok(parsed)
+}
+
+/// Gas used by each test case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasSnapshot {
+    entries: BTreeMap<String, u64>,
+}
+
+impl GasSnapshot {
+    /// Load the snapshot at `path`, empty if it does not exist.
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("Could not read gas snapshot {}", path.display()))
+            }
+        };
+
+        let entries = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.strip_suffix(')')
+                    .and_then(|line| line.split_once(" (gas: "))
+                    .and_then(|(name, gas)| Some((name.to_string(), gas.parse().ok()?)))
+                    .with_context(|| format!("Invalid gas snapshot line {line}"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { entries })
+    }
+
+    /// Write the snapshot to `path`, sorted by test case.
+    pub fn write(&self, path: &Path) -> Result<(), anyhow::Error> {
+        let content: String = self
+            .entries
+            .iter()
+            .map(|(name, gas)| format!("{name} (gas: {gas})\n"))
+            .collect();
+        fs::write(path, content)
+            .with_context(|| format!("Could not write gas snapshot {}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.entries.get(name).copied()
+    }
+
+    pub fn insert(&mut self, name: String, gas: u64) {
+        self.entries.insert(name, gas);
+    }
+
+    /// Keep only the entries of the test cases `keep` returns true for.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.entries.retain(|name, _| keep(name));
+    }
+
+    /// Compare the gas used by a test case with the snapshot, if it has an entry.
+    pub fn diff(&self, name: &str, gas: u64) -> Option<GasDiff> {
+        self.get(name).map(|before| GasDiff {
+            name: name.to_string(),
+            before,
+            after: gas,
+        })
+    }
+}
+
+/// Change of the gas used by a test case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasDiff {
+    pub name: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl GasDiff {
+    /// Change of the gas used, in percent of the snapshot.
+    pub fn change(&self) -> f64 {
+        if self.before == 0 {
+            return if self.after == 0 { 0.0 } else { f64::INFINITY };
+        }
+        (self.after as f64 - self.before as f64) / self.before as f64 * 100.0
+    }
+
+    /// Whether the gas moved by more than `tolerance` percent.
+    pub fn exceeds(&self, tolerance: f64) -> bool {
+        self.change().abs() > tolerance
+    }
+}
+
+impl fmt::Display for GasDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (gas: {} -> {}, {:+} / {:+.2}%)",
+            self.name,
+            self.before,
+            self.after,
+            self.after as i128 - self.before as i128,
+            self.change()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn load_missing_snapshot_is_empty() {
+        let dir = TempDir::new().unwrap();
+        let snapshot = GasSnapshot::load(&dir.path().join(".gas-snapshot")).unwrap();
+        assert_eq!(snapshot, GasSnapshot::default());
+    }
+
+    #[test]
+    fn write_and_load_snapshot() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".gas-snapshot");
+        let mut snapshot = GasSnapshot::default();
+        snapshot.insert(String::from("whitelist::TestHappyPath"), 1234);
+        snapshot.insert(String::from("scenario::whitelist"), 42);
+        snapshot.write(&path).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "scenario::whitelist (gas: 42)\nwhitelist::TestHappyPath (gas: 1234)\n"
+        );
+        assert_eq!(GasSnapshot::load(&path).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn load_invalid_snapshot_fails() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".gas-snapshot");
+        fs::write(&path, "whitelist::TestHappyPath 1234\n").unwrap();
+
+        assert!(GasSnapshot::load(&path).is_err());
+    }
+
+    #[test]
+    fn parse_valid_tolerances() {
+        assert_eq!(parse_tolerance("2").unwrap(), 2.0);
+        assert_eq!(parse_tolerance("0.5").unwrap(), 0.5);
+        assert_eq!(parse_tolerance("0").unwrap(), 0.0);
+    }
+
+    #[test]
+    fn parse_invalid_tolerances_fails() {
+        for tolerance in ["NaN", "inf", "-inf", "-1", "2%"] {
+            assert!(
+                parse_tolerance(tolerance).is_err(),
+                "{tolerance} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn diff_of_missing_case() {
+        let snapshot = GasSnapshot::default();
+        assert_eq!(snapshot.diff("whitelist::TestHappyPath", 1234), None);
+    }
+
+    #[test]
+    fn exceeds_at_tolerance_boundary() {
+        let diff = |after| GasDiff {
+            name: String::from("whitelist::TestHappyPath"),
+            before: 200,
+            after,
+        };
+
+        assert_eq!(diff(202).change(), 1.0);
+        assert!(!diff(202).exceeds(1.0));
+        assert!(diff(203).exceeds(1.0));
+        assert!(!diff(198).exceeds(1.0));
+        assert!(diff(197).exceeds(1.0));
+        assert!(!diff(200).exceeds(0.0));
+        assert!(diff(201).exceeds(0.0));
+    }
+
+    #[test]
+    fn exceeds_from_zero() {
+        let diff = GasDiff {
+            name: String::from("whitelist::TestHappyPath"),
+            before: 0,
+            after: 1,
+        };
+        assert!(diff.exceeds(100.0));
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Symbolication of wasm backtraces with the debug artifacts of the actors.
+//!
+//! Debug artifacts are built with the same profile as the deployed ones, only keeping the name
+//! section and DWARF. Their code section is compared with the one of the module that ran, and they
+//! are only used when both are identical. The sections before it may differ, so backtrace offsets
+//! are rebased on the code section of the deployed module.
+//!
+//! The modules of the actors share code offsets and function indices, so a wasm frame is only
+//! symbolized with the module it names, or with the actor of the FVM backtrace frame it is part of.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use addr2line::gimli::{self, EndianRcSlice, RunTimeEndian};
+use addr2line::Context;
+use fvm_shared::ActorID;
+use regex::{Captures, Regex};
+use wasmparser::{Name, NameSectionReader, Parser, Payload};
+
+use crate::size::code_range;
+
+/// A frame of an FVM backtrace, e.g. `00: f0101 (method 3) -- ...`, or of a wasmtime backtrace
+/// within it, e.g. `0: 0x2d0a - <unknown>!<wasm function 90>`, capturing the module name.
+const FRAME_PATTERN: &str =
+    r"[ft]0(\d+) \(method \d+\)|0x([0-9a-f]+) - ([^!\n]*)!<wasm function (\d+)>";
+
+/// Module name of wasmtime frames of modules without one.
+const UNKNOWN_MODULE: &str = "<unknown>";
+
+/// Function names and source locations of a debug wasm module.
+pub struct Symbolizer {
+    name: String,
+    /// Actor the module is deployed at.
+    actor: ActorID,
+    /// Module name of the name section.
+    module: Option<String>,
+    functions: HashMap<u32, String>,
+    /// Range of the code section in the deployed module, which backtrace offsets are in. DWARF
+    /// addresses are relative to its start.
+    code: (u64, u64),
+    dwarf: Option<Context<EndianRcSlice<RunTimeEndian>>>,
+}
+
+impl Symbolizer {
+    /// Load the symbols of the debug module `wasm` of the actor `name` deployed at `actor`.
+    ///
+    /// `None` if its code differs from the `deployed` module, its offsets not matching.
+    pub fn new(
+        name: &str,
+        actor: ActorID,
+        wasm: &[u8],
+        deployed: &[u8],
+    ) -> Result<Option<Self>, anyhow::Error> {
+        let code = code_range(deployed)?;
+        if wasm[code_range(wasm)?] != deployed[code.clone()] {
+            return Ok(None);
+        }
+
+        let mut module = None;
+        let mut functions = HashMap::new();
+        let mut sections: HashMap<&str, &[u8]> = HashMap::new();
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::CustomSection(reader) if reader.name() == "name" => {
+                    for name in NameSectionReader::new(reader.data(), reader.data_offset()) {
+                        match name? {
+                            Name::Module { name, .. } => module = Some(name.to_string()),
+                            Name::Function(map) => {
+                                for naming in map {
+                                    let naming = naming?;
+                                    functions.insert(naming.index, naming.name.to_string());
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Payload::CustomSection(reader) if reader.name().starts_with(".debug_") => {
+                    sections.insert(reader.name(), reader.data());
+                }
+                _ => {}
+            }
+        }
+
+        let dwarf = if sections.is_empty() {
+            None
+        } else {
+            let dwarf = gimli::Dwarf::load(|id| -> Result<_, gimli::Error> {
+                let data = sections.get(id.name()).copied().unwrap_or_default();
+                Ok(EndianRcSlice::new(Rc::from(data), RunTimeEndian::Little))
+            })?;
+            Some(Context::from_dwarf(dwarf)?)
+        };
+
+        Ok(Some(Self {
+            name: name.to_string(),
+            actor,
+            module,
+            functions,
+            code: (code.start as u64, code.end as u64),
+            dwarf,
+        }))
+    }
+
+    /// Whether the frames of `module` belong to this module.
+    fn is_module(&self, module: &str) -> bool {
+        module == self.name || self.module.as_deref() == Some(module)
+    }
+
+    /// Describe the code at `offset` in the module, in function `index`, if it is in this module.
+    fn describe(&self, offset: u64, index: u32) -> Option<String> {
+        if offset < self.code.0 || offset >= self.code.1 {
+            return None;
+        }
+        let function = self.functions.get(&index)?;
+
+        let location = self
+            .dwarf
+            .as_ref()
+            .and_then(|dwarf| dwarf.find_location(offset - self.code.0).ok().flatten())
+            .and_then(|location| Some(format!(" at {}:{}", location.file?, location.line?)))
+            .unwrap_or_default();
+        Some(format!("{function}{location} [{}]", self.name))
+    }
+}
+
+/// Append function names and source locations to the frames of the wasm backtraces in
+/// `message`.
+///
+/// Each wasm frame is symbolized with the module it names, or else with the module of the actor
+/// of the last FVM backtrace frame before it. Frames of other actors are left as they are.
+pub fn symbolize<'a>(message: &'a str, symbolizers: &[Symbolizer]) -> Cow<'a, str> {
+    if symbolizers.is_empty() {
+        return Cow::Borrowed(message);
+    }
+
+    let pattern = Regex::new(FRAME_PATTERN).expect("valid frame pattern");
+    let mut actor: Option<ActorID> = None;
+    pattern.replace_all(message, |captures: &Captures| {
+        let frame = &captures[0];
+        if let Some(id) = captures.get(1) {
+            actor = id.as_str().parse().ok();
+            return frame.to_string();
+        }
+
+        let module = &captures[3];
+        let symbolizer = if module == UNKNOWN_MODULE {
+            actor.and_then(|actor| symbolizers.iter().find(|s| s.actor == actor))
+        } else {
+            symbolizers.iter().find(|s| s.is_module(module))
+        };
+        let offset = u64::from_str_radix(&captures[2], 16).ok();
+        let index = captures[4].parse().ok();
+        let description = symbolizer
+            .zip(offset.zip(index))
+            .and_then(|(symbolizer, (offset, index))| symbolizer.describe(offset, index));
+        match description {
+            Some(description) => format!("{frame} -> {description}"),
+            None => frame.to_string(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A module whose functions are named `{prefix}_*` when `named`, with the same code otherwise.
+    fn module(prefix: &str, named: bool) -> Vec<u8> {
+        let name = |function: &str| {
+            if named {
+                format!("${prefix}_{function}")
+            } else {
+                String::new()
+            }
+        };
+        wat::parse_str(format!(
+            r#"(module
+                (func {} (param i32) (result i32) local.get 0 i32.const 1 i32.add)
+                (func {} (export "invoke") (param i32) (result i32) local.get 0 call 0))"#,
+            name("add_one"),
+            name("invoke"),
+        ))
+        .unwrap()
+    }
+
+    fn code_start(wasm: &[u8]) -> usize {
+        code_range(wasm).unwrap().start
+    }
+
+    /// `wasm` with a custom section inserted before all of its sections.
+    fn with_custom_section(wasm: &[u8]) -> Vec<u8> {
+        let (header, sections) = wasm.split_at(8);
+        let section = [&[0, 9, 5][..], b"extra", b"abc"].concat();
+        [header, &section, sections].concat()
+    }
+
+    #[test]
+    fn known_frames_use_their_actor_module() {
+        let target = Symbolizer::new(
+            "Whitelist",
+            101,
+            &module("target", true),
+            &module("", false),
+        )
+        .unwrap()
+        .unwrap();
+        let test = Symbolizer::new(
+            "Whitelist.t",
+            102,
+            &module("test", true),
+            &module("", false),
+        )
+        .unwrap()
+        .unwrap();
+        let offset = code_start(&module("", false)) + 2;
+
+        let message = format!(
+            "00: f0102 (method 3) -- wasm trap\nwasm backtrace:\n    0: {offset:#x} - <unknown>!<wasm function 0>\n (4)\n\
+             01: f0101 (method 2) -- wasm trap\nwasm backtrace:\n    0: {offset:#x} - <unknown>!<wasm function 1>\n (4)\n"
+        );
+        let symbolized = symbolize(&message, &[target, test]);
+
+        assert_eq!(
+            symbolized,
+            format!(
+                "00: f0102 (method 3) -- wasm trap\nwasm backtrace:\n    0: {offset:#x} - <unknown>!<wasm function 0> -> test_add_one [Whitelist.t]\n (4)\n\
+                 01: f0101 (method 2) -- wasm trap\nwasm backtrace:\n    0: {offset:#x} - <unknown>!<wasm function 1> -> target_invoke [Whitelist]\n (4)\n"
+            )
+        );
+    }
+
+    #[test]
+    fn frames_without_actor_are_left_as_is() {
+        let target = Symbolizer::new(
+            "Whitelist",
+            101,
+            &module("target", true),
+            &module("", false),
+        )
+        .unwrap()
+        .unwrap();
+        let offset = code_start(&module("", false)) + 2;
+
+        let message = format!("    0: {offset:#x} - <unknown>!<wasm function 0>");
+        assert_eq!(symbolize(&message, &[target]), message);
+    }
+
+    #[test]
+    fn frames_naming_their_module() {
+        let target = Symbolizer::new(
+            "Whitelist",
+            101,
+            &module("target", true),
+            &module("", false),
+        )
+        .unwrap()
+        .unwrap();
+        let offset = code_start(&module("", false)) + 2;
+
+        let message = format!("00: f0102 (method 3) -- {offset:#x} - Whitelist!<wasm function 0>");
+        assert_eq!(
+            symbolize(&message, &[target]),
+            format!("{message} -> target_add_one [Whitelist]")
+        );
+    }
+
+    #[test]
+    fn debug_module_with_other_sections_is_rebased() {
+        let debug = with_custom_section(&module("target", true));
+        assert_ne!(code_start(&debug), code_start(&module("", false)));
+        let target = Symbolizer::new("Whitelist", 101, &debug, &module("", false))
+            .unwrap()
+            .unwrap();
+        let offset = code_start(&module("", false)) + 2;
+
+        let message = format!("    0: {offset:#x} - Whitelist!<wasm function 1>");
+        assert_eq!(
+            symbolize(&message, &[target]),
+            format!("{message} -> target_invoke [Whitelist]")
+        );
+    }
+
+    #[test]
+    fn debug_module_with_other_code_is_skipped() {
+        let other = wat::parse_str(
+            r#"(module (func $invoke (export "invoke") (param i32) (result i32) local.get 0))"#,
+        )
+        .unwrap();
+
+        assert!(
+            Symbolizer::new("Whitelist", 101, &other, &module("", false))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,311 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Call trees of the sends executed by a test, built from the FVM execution trace.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use fvm::trace::ExecutionEvent;
+use fvm_ipld_encoding::ipld_block::IpldBlock;
+use fvm_shared::address::Address;
+use fvm_shared::error::ExitCode;
+use fvm_shared::{ActorID, MethodNum};
+use kythera_lib::{Abi, Method};
+
+/// Methods of the Kythera cheatcodes actor.
+pub(crate) const CHEATCODES: &[&str] = &[
+    "Warp", "Epoch", "Fee", "ChainId", "Prank", "Trick", "Deal", "Alter",
+];
+
+/// A send and the nested sends it made.
+#[derive(Debug, Clone)]
+pub struct CallTrace {
+    pub from: ActorID,
+    pub to: Address,
+    pub method: MethodNum,
+    pub params: Option<IpldBlock>,
+    /// Gas used by the call, including its nested calls.
+    pub gas_used: u64,
+    /// Exit code of the call, if it returned.
+    pub exit_code: Option<ExitCode>,
+    pub return_data: Option<IpldBlock>,
+    /// Syscall error of the call, if it could not be made.
+    pub error: Option<String>,
+    pub calls: Vec<CallTrace>,
+}
+
+impl CallTrace {
+    /// Build the call trees of an execution trace.
+    pub fn from_events(events: &[ExecutionEvent]) -> Vec<CallTrace> {
+        let mut roots = Vec::new();
+        let mut stack: Vec<CallTrace> = Vec::new();
+
+        for event in events {
+            match event {
+                ExecutionEvent::Call {
+                    from,
+                    to,
+                    method,
+                    params,
+                    ..
+                } => stack.push(CallTrace {
+                    from: *from,
+                    to: *to,
+                    method: *method,
+                    params: params.clone(),
+                    gas_used: 0,
+                    exit_code: None,
+                    return_data: None,
+                    error: None,
+                    calls: Vec::new(),
+                }),
+                ExecutionEvent::GasCharge(charge) => {
+                    if let Some(call) = stack.last_mut() {
+                        call.gas_used += charge.total().round_up();
+                    }
+                }
+                ExecutionEvent::CallReturn(exit_code, return_data) => {
+                    if let Some(mut call) = stack.pop() {
+                        call.exit_code = Some(*exit_code);
+                        call.return_data = return_data.clone();
+                        attach(&mut stack, &mut roots, call);
+                    }
+                }
+                ExecutionEvent::CallError(err) => {
+                    if let Some(mut call) = stack.pop() {
+                        call.error = Some(format!("{} ({})", err.0, err.1));
+                        attach(&mut stack, &mut roots, call);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Calls interrupted by an abort never return.
+        while let Some(call) = stack.pop() {
+            attach(&mut stack, &mut roots, call);
+        }
+        roots
+    }
+}
+
+fn attach(stack: &mut [CallTrace], roots: &mut Vec<CallTrace>, call: CallTrace) {
+    match stack.last_mut() {
+        Some(parent) => {
+            parent.gas_used += call.gas_used;
+            parent.calls.push(call);
+        }
+        None => roots.push(call),
+    }
+}
+
+/// Names of the methods that can appear in a trace, resolved from their number.
+#[derive(Debug, Clone, Default)]
+pub struct MethodNames(HashMap<MethodNum, String>);
+
+impl MethodNames {
+    /// Names of the methods of the given Abis and of the cheatcodes.
+    pub fn new<'a>(abis: impl IntoIterator<Item = &'a Abi>) -> Self {
+        let mut names = Self::default();
+        for name in CHEATCODES.iter().copied().chain(["Constructor"]) {
+            if let Ok(method) = Method::new_from_name(name) {
+                names.0.insert(method.number(), name.to_string());
+            }
+        }
+        for abi in abis {
+            let methods = abi
+                .constructor
+                .iter()
+                .chain(abi.set_up.iter())
+                .chain(abi.methods.iter());
+            for method in methods {
+                names.0.insert(method.number(), method.name().to_string());
+            }
+        }
+        names
+    }
+
+    pub fn get(&self, method: MethodNum) -> Option<&str> {
+        self.0.get(&method).map(String::as_str)
+    }
+}
+
+/// Render call trees, one line per call and per return.
+pub fn render(traces: &[CallTrace], names: &MethodNames) -> String {
+    let mut out = String::new();
+    for trace in traces {
+        render_call(&mut out, trace, names, "", "");
+    }
+    out
+}
+
+fn render_call(out: &mut String, call: &CallTrace, names: &MethodNames, first: &str, rest: &str) {
+    let method = names
+        .get(call.method)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("method {}", call.method));
+    let _ = writeln!(
+        out,
+        "{first}[{}] {} -> {}::{method}({})",
+        call.gas_used,
+        call.from,
+        call.to,
+        call.params
+            .as_ref()
+            .map(|block| decode(&block.data))
+            .unwrap_or_default()
+    );
+
+    let child_rest = format!("{rest}│  ");
+    for nested in &call.calls {
+        render_call(out, nested, names, &format!("{rest}├─ "), &child_rest);
+    }
+
+    let ret = match (&call.error, call.exit_code) {
+        (Some(err), _) => format!("error: {err}"),
+        (None, Some(exit_code)) => format!(
+            "exit code {}{}",
+            exit_code.value(),
+            call.return_data
+                .as_ref()
+                .map(|block| format!(": {}", decode(&block.data)))
+                .unwrap_or_default()
+        ),
+        (None, None) => String::from("aborted"),
+    };
+    let _ = writeln!(out, "{rest}└─ ← {ret}");
+}
+
+/// Display DAG-CBOR data as JSON, falling back to its hex encoding.
+///
+/// Abis only name methods, so values are shown from their data model rather than their types:
+/// tuples are arrays, and data holding byte strings, which JSON has no type for, is shown as hex.
+pub(crate) fn decode(data: &[u8]) -> String {
+    match fvm_ipld_encoding::from_slice::<serde_json::Value>(data) {
+        Ok(value) => value.to_string(),
+        Err(_) => data.iter().fold(String::from("0x"), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm::gas::{Gas, GasCharge};
+    use fvm::kernel::SyscallError;
+    use fvm_shared::econ::TokenAmount;
+    use fvm_shared::error::ErrorNumber;
+
+    use super::*;
+
+    fn call(from: ActorID, to: ActorID, method: &str, params: Option<IpldBlock>) -> ExecutionEvent {
+        ExecutionEvent::Call {
+            from,
+            to: Address::new_id(to),
+            method: Method::new_from_name(method).unwrap().number(),
+            params,
+            value: TokenAmount::from_atto(0),
+        }
+    }
+
+    fn gas(gas: u64) -> ExecutionEvent {
+        ExecutionEvent::GasCharge(GasCharge::new(
+            "OnMethodInvocation",
+            Gas::new(gas),
+            Gas::new(0),
+        ))
+    }
+
+    fn cbor<T: serde::Serialize>(value: &T) -> Option<IpldBlock> {
+        IpldBlock::serialize_cbor(value).unwrap()
+    }
+
+    fn names() -> MethodNames {
+        let abi = |method: &str| Abi {
+            constructor: None,
+            set_up: None,
+            methods: vec![Method::new_from_name(method).unwrap()],
+        };
+        MethodNames::new(&[abi("TestFailNotAdmin"), abi("SetWhitelist")])
+    }
+
+    /// A test method calling its target, which fails, and returning.
+    fn events() -> Vec<ExecutionEvent> {
+        vec![
+            call(101, 102, "TestFailNotAdmin", cbor(&100u64)),
+            gas(2000),
+            call(102, 100, "SetWhitelist", cbor(&(1u64, true))),
+            gas(1200),
+            ExecutionEvent::CallReturn(ExitCode::USR_FORBIDDEN, None),
+            gas(50),
+            ExecutionEvent::CallReturn(ExitCode::OK, cbor(&7u64)),
+        ]
+    }
+
+    #[test]
+    fn nested_calls_sum_their_gas() {
+        let traces = CallTrace::from_events(&events());
+
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].gas_used, 3250);
+        assert_eq!(traces[0].exit_code, Some(ExitCode::OK));
+        assert_eq!(traces[0].calls.len(), 1);
+        assert_eq!(traces[0].calls[0].to, Address::new_id(100));
+        assert_eq!(traces[0].calls[0].gas_used, 1200);
+        assert_eq!(traces[0].calls[0].exit_code, Some(ExitCode::USR_FORBIDDEN));
+    }
+
+    #[test]
+    fn render_call_tree() {
+        let rendered = render(&CallTrace::from_events(&events()), &names());
+
+        assert_eq!(
+            rendered,
+            "[3250] 101 -> f0102::TestFailNotAdmin(100)\n\
+             ├─ [1200] 102 -> f0100::SetWhitelist([1,true])\n\
+             │  └─ ← exit code 18\n\
+             └─ ← exit code 0: 7\n"
+        );
+    }
+
+    #[test]
+    fn render_errors_and_aborts() {
+        let events = [
+            call(101, 102, "TestFailNotAdmin", None),
+            gas(10),
+            call(102, 103, "SetWhitelist", None),
+            ExecutionEvent::CallError(SyscallError(
+                String::from("actor does not exist"),
+                ErrorNumber::NotFound,
+            )),
+        ];
+        let rendered = render(&CallTrace::from_events(&events), &names());
+        let lines = rendered.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "[10] 101 -> f0102::TestFailNotAdmin()");
+        assert_eq!(lines[1], "├─ [0] 102 -> f0103::SetWhitelist()");
+        assert!(lines[2].starts_with("│  └─ ← error: actor does not exist ("));
+        assert_eq!(lines[3], "└─ ← aborted");
+    }
+
+    #[test]
+    fn unknown_methods_are_numbered() {
+        let events = [call(101, 102, "Unknown", None)];
+        let method = Method::new_from_name("Unknown").unwrap().number();
+
+        assert!(render(&CallTrace::from_events(&events), &names())
+            .starts_with(&format!("[0] 101 -> f0102::method {method}()")));
+    }
+
+    #[test]
+    fn decode_data_model() {
+        let tuple = cbor(&(1u64, "admin")).unwrap();
+        let address = cbor(&Address::new_id(101)).unwrap();
+
+        assert_eq!(decode(&tuple.data), r#"[1,"admin"]"#);
+        assert_eq!(decode(&address.data), "0x420065");
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Validation of the built wasm modules against the constraints of the FVM.
+//!
+//! Also compiled into the build script, which validates the actors before publishing them.
+
+use anyhow::bail;
+use wasmparser::{
+    ExternalKind, FuncType, Parser, Payload, Type, TypeRef, ValType, Validator, WasmFeatures,
+};
+
+/// Maximum size of an IPLD block in the FVM, which the code of an actor is stored in.
+pub const MAX_MODULE_SIZE: usize = 1 << 20;
+
+/// Modules of the syscalls exposed by the FVM.
+const SYSCALL_MODULES: &[&str] = &[
+    "actor", "crypto", "debug", "event", "gas", "ipld", "network", "rand", "self", "send", "vm",
+];
+
+/// Wasm features supported by the FVM, listed exhaustively so that none is left to the defaults of
+/// wasmparser.
+fn features() -> WasmFeatures {
+    WasmFeatures {
+        mutable_global: true,
+        sign_extension: true,
+        bulk_memory: true,
+        // Floats are rejected by the FVM, including their saturating conversions.
+        floats: false,
+        saturating_float_to_int: false,
+        multi_value: false,
+        reference_types: false,
+        simd: false,
+        relaxed_simd: false,
+        threads: false,
+        tail_call: false,
+        multi_memory: false,
+        exceptions: false,
+        memory64: false,
+        extended_const: false,
+        component_model: false,
+        function_references: false,
+        memory_control: false,
+        gc: false,
+    }
+}
+
+/// Check that `wasm` is a module the FVM can run as an actor, listing every violation.
+pub fn validate_module(wasm: &[u8]) -> Result<(), anyhow::Error> {
+    let mut violations = Vec::new();
+
+    if wasm.len() > MAX_MODULE_SIZE {
+        violations.push(format!(
+            "module is {} bytes, over the {MAX_MODULE_SIZE} bytes limit",
+            wasm.len()
+        ));
+    }
+
+    // Floats, multi-value, SIMD and threads are rejected as unsupported features.
+    if let Err(err) = Validator::new_with_features(features()).validate_all(wasm) {
+        violations.push(format!("unsupported wasm: {err}"));
+    }
+
+    let mut types: Vec<Option<FuncType>> = Vec::new();
+    let mut functions: Vec<u32> = Vec::new();
+    let mut invoke = None;
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::TypeSection(reader) => {
+                for ty in reader {
+                    match ty? {
+                        Type::Func(func) => types.push(Some(func)),
+                        _ => types.push(None),
+                    }
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    if !SYSCALL_MODULES.contains(&import.module) {
+                        violations.push(format!(
+                            "imports {}::{} from unknown module {}",
+                            import.module, import.name, import.module
+                        ));
+                    }
+                    if let TypeRef::Func(ty) = import.ty {
+                        functions.push(ty);
+                    }
+                }
+            }
+            Payload::FunctionSection(reader) => {
+                for ty in reader {
+                    functions.push(ty?);
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    if export.name == "invoke" && export.kind == ExternalKind::Func {
+                        invoke = Some(export.index);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let invoke_type = invoke
+        .and_then(|index| functions.get(index as usize))
+        .and_then(|ty| types.get(*ty as usize))
+        .and_then(Option::as_ref);
+    match (invoke, invoke_type) {
+        (None, _) => violations.push(String::from("does not export an invoke function")),
+        (Some(_), Some(ty)) if ty.params() == [ValType::I32] && ty.results() == [ValType::I32] => {}
+        (Some(_), ty) => violations.push(format!(
+            "exports invoke as {}, expected fn(i32) -> i32",
+            ty.map(|ty| format!("{ty:?}"))
+                .unwrap_or_else(|| String::from("an unknown type"))
+        )),
+    }
+
+    if !violations.is_empty() {
+        bail!("\n  - {}", violations.join("\n  - "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check the module `wat`, returning its violations.
+    fn violations(wat: &str) -> String {
+        let wasm = wat::parse_str(wat).unwrap();
+        match validate_module(&wasm) {
+            Ok(()) => String::new(),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn valid_module() {
+        assert_eq!(
+            violations(
+                r#"(module
+                    (import "ipld" "block_open" (func (param i32 i32) (result i32)))
+                    (func (export "invoke") (param i32) (result i32) local.get 0))"#
+            ),
+            ""
+        );
+    }
+
+    #[test]
+    fn missing_invoke() {
+        let violations =
+            violations(r#"(module (func (export "run") (param i32) (result i32) local.get 0))"#);
+        assert!(
+            violations.contains("does not export an invoke function"),
+            "{violations}"
+        );
+    }
+
+    #[test]
+    fn wrong_invoke_signature() {
+        let violations = violations(r#"(module (func (export "invoke") (param i32)))"#);
+        assert!(
+            violations.contains("exports invoke as")
+                && violations.contains("expected fn(i32) -> i32"),
+            "{violations}"
+        );
+    }
+
+    #[test]
+    fn import_from_unknown_module() {
+        let violations = violations(
+            r#"(module
+                (import "env" "abort" (func))
+                (func (export "invoke") (param i32) (result i32) local.get 0))"#,
+        );
+        assert!(
+            violations.contains("imports env::abort from unknown module env"),
+            "{violations}"
+        );
+    }
+
+    #[test]
+    fn module_too_large() {
+        let data = "a".repeat(MAX_MODULE_SIZE);
+        let violations = violations(&format!(
+            r#"(module
+                (memory 17)
+                (data (i32.const 0) "{data}")
+                (func (export "invoke") (param i32) (result i32) local.get 0))"#
+        ));
+        assert!(
+            violations.contains("over the 1048576 bytes limit"),
+            "{violations}"
+        );
+    }
+
+    #[test]
+    fn multi_value_rejected() {
+        let violations = violations(
+            r#"(module
+                (func $pair (result i32 i32) i32.const 0 i32.const 1)
+                (func (export "invoke") (param i32) (result i32) local.get 0))"#,
+        );
+        assert!(violations.contains("unsupported wasm"), "{violations}");
+    }
+
+    #[test]
+    fn floats_rejected() {
+        let violations = violations(
+            r#"(module
+                (func (export "invoke") (param i32) (result i32)
+                    f32.const 1 f32.const 2 f32.add drop local.get 0))"#,
+        );
+        assert!(violations.contains("unsupported wasm"), "{violations}");
+    }
+}
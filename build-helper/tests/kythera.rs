@@ -0,0 +1,200 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Run the Kythera tests of the workspace, one libtest case per test actor method and per scenario
+//! of the `scenarios` dir.
+//!
+//! JUnit XML and JSON reports of the executed cases are written to `$KYTHERA_REPORT_DIR`, which
+//! defaults to `target/kythera`. The gas used by the cases is recorded in or checked against the
+//! `.gas-snapshot` file depending on `$KYTHERA_GAS_SNAPSHOT`, see [`SnapshotMode`].
+//!
+//! The call trace of failed cases is printed along with their failure, and the one of every case
+//! when `$KYTHERA_VERBOSE` is set.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use build_helper::artifacts::{artifacts_dir, load_pairs};
+use build_helper::report::{Report, TestRecord};
+use build_helper::runner::{test_cases, TestOutcome, TestStatus};
+use build_helper::scenario::{load_scenarios, scenarios_dir};
+use build_helper::snapshot::{snapshot_path, GasDiff, GasSnapshot, SnapshotMode};
+use build_helper::trace::{self, MethodNames};
+use libtest_mimic::{Arguments, Trial};
+
+fn report_dir() -> PathBuf {
+    std::env::var_os("KYTHERA_REPORT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/kythera"))
+}
+
+/// State shared by the trials.
+#[derive(Clone)]
+struct Harness {
+    report: Arc<Mutex<Report>>,
+    snapshot_mode: Option<SnapshotMode>,
+    snapshot: Arc<Mutex<GasSnapshot>>,
+    diffs: Arc<Mutex<Vec<GasDiff>>>,
+    verbose: bool,
+}
+
+impl Harness {
+    /// A trial running a test case, checking its gas against the snapshot and reporting it.
+    fn trial(
+        &self,
+        name: String,
+        method_names: MethodNames,
+        run: impl FnOnce() -> Result<TestOutcome, anyhow::Error> + Send + 'static,
+        record: impl FnOnce(&TestOutcome, Duration) -> TestRecord + Send + 'static,
+    ) -> Trial {
+        let harness = self.clone();
+        Trial::test(name.clone(), move || {
+            let start = Instant::now();
+            let mut outcome = run().unwrap_or_else(|err| TestOutcome::erred(&err));
+            let duration = start.elapsed();
+            if outcome.passed() {
+                if let Err(failure) = harness.check_gas(&name, &outcome) {
+                    outcome.status = TestStatus::Failed;
+                    outcome.message = Some(failure);
+                }
+            }
+            harness
+                .report
+                .lock()
+                .unwrap()
+                .tests
+                .push(record(&outcome, duration));
+
+            let trace = trace::render(&outcome.trace, &method_names);
+            if !outcome.passed() {
+                return Err(format!("{outcome}\n{trace}").into());
+            }
+            if harness.verbose {
+                println!("{name}:\n{trace}");
+            }
+            Ok(())
+        })
+    }
+
+    /// Record the gas used by a passed case, or check it against the snapshot.
+    fn check_gas(&self, name: &str, outcome: &TestOutcome) -> Result<(), String> {
+        let gas_used = match outcome.gas_used {
+            Some(gas_used) => gas_used,
+            None => return Ok(()),
+        };
+        match self.snapshot_mode {
+            Some(SnapshotMode::Write) => {
+                self.snapshot
+                    .lock()
+                    .unwrap()
+                    .insert(name.to_string(), gas_used);
+            }
+            Some(SnapshotMode::Check { tolerance }) => {
+                let diff = self
+                    .snapshot
+                    .lock()
+                    .unwrap()
+                    .diff(name, gas_used)
+                    .ok_or_else(|| {
+                        format!(
+                            "no gas snapshot of {name} (gas: {gas_used}), \
+                             record it with KYTHERA_GAS_SNAPSHOT=write"
+                        )
+                    })?;
+                let exceeds = diff.exceeds(tolerance);
+                self.diffs.lock().unwrap().push(diff.clone());
+                if exceeds {
+                    return Err(format!("gas changed by more than {tolerance}%: {diff}"));
+                }
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let args = Arguments::from_args();
+    let harness = Harness {
+        report: Arc::default(),
+        snapshot_mode: SnapshotMode::from_env().expect("Invalid gas snapshot configuration"),
+        snapshot: Arc::new(Mutex::new(
+            GasSnapshot::load(&snapshot_path()).expect("Could not load gas snapshot"),
+        )),
+        diffs: Arc::default(),
+        verbose: std::env::var_os("KYTHERA_VERBOSE").is_some(),
+    };
+
+    let pairs = load_pairs(&artifacts_dir()).expect("Could not load actors from artifacts");
+    let mut trials = test_cases(&pairs)
+        .into_iter()
+        .map(|case| {
+            let record_case = case.clone();
+            harness.trial(
+                case.name(),
+                case.method_names(),
+                move || case.run(),
+                move |outcome, duration| TestRecord::new(&record_case, outcome, duration),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let scenarios = load_scenarios(&scenarios_dir()).expect("Could not load scenarios");
+    trials.extend(scenarios.into_iter().map(|scenario| {
+        let record_scenario = scenario.clone();
+        harness.trial(
+            scenario.case_name(),
+            scenario.method_names(&artifacts_dir()),
+            move || scenario.run(),
+            move |outcome, duration| TestRecord::scenario(&record_scenario, outcome, duration),
+        )
+    }));
+
+    let case_names = trials
+        .iter()
+        .map(|trial| trial.name().to_string())
+        .collect::<BTreeSet<_>>();
+    let conclusion = libtest_mimic::run(&args, trials);
+
+    let report = harness.report.lock().unwrap();
+    if !report.tests.is_empty() {
+        let dir = report_dir();
+        fs::create_dir_all(&dir).expect("Could not create report dir");
+        report
+            .write_json(&dir.join("report.json"))
+            .expect("Could not write JSON report");
+        report
+            .write_junit(&dir.join("junit.xml"))
+            .expect("Could not write JUnit report");
+    }
+
+    match harness.snapshot_mode {
+        Some(SnapshotMode::Write) => {
+            // Drop the entries of removed or renamed cases, keeping those filtered out of the run.
+            let mut snapshot = harness.snapshot.lock().unwrap();
+            snapshot.retain(|name| case_names.contains(name));
+            snapshot
+                .write(&snapshot_path())
+                .expect("Could not write gas snapshot");
+        }
+        Some(SnapshotMode::Check { .. }) => {
+            let diffs = harness.diffs.lock().unwrap();
+            let changed = diffs
+                .iter()
+                .filter(|diff| diff.before != diff.after)
+                .collect::<Vec<_>>();
+            if !changed.is_empty() {
+                println!("gas changes:");
+                for diff in changed {
+                    println!("    {diff}");
+                }
+            }
+        }
+        None => {}
+    }
+
+    conclusion.exit();
+}
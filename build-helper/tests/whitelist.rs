@@ -0,0 +1,73 @@
+// Copyright 2023 Polyphene.
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! Host side counterpart of `tests/whitelist-test`.
+
+use std::collections::HashMap;
+
+use build_helper::env::TestEnv;
+use fvm_shared::error::ExitCode;
+use whitelist_types::{ActorState, Address, ConstructorParams, SetWhitelistParams};
+
+#[test]
+fn not_admin_forbidden() -> Result<(), anyhow::Error> {
+    let mut env = TestEnv::new()?;
+    let admin = Address::new_id(env.account("alice")?.id);
+    let whitelist = env.deploy_with_params("Whitelist", &ConstructorParams { admin })?;
+
+    let params = SetWhitelistParams {
+        address: Address::new_id(env.account("bob")?.id),
+        whitelisted: true,
+    };
+    env.call_as("bob", whitelist, "SetWhitelist", &params)?
+        .assert_exit_code(ExitCode::USR_FORBIDDEN);
+
+    Ok(())
+}
+
+#[test]
+fn happy_path() -> Result<(), anyhow::Error> {
+    let mut env = TestEnv::new()?;
+    let sender = Address::new_id(env.sender());
+    let whitelist = env.deploy_with_params("Whitelist", &ConstructorParams { admin: sender })?;
+
+    let params = SetWhitelistParams {
+        address: sender,
+        whitelisted: true,
+    };
+    env.call(whitelist, "SetWhitelist", &params)?
+        .assert_success();
+    env.call(whitelist, "IsWhitelisted", &sender)?
+        .assert_return_eq(true);
+
+    env.assert_state_eq(
+        whitelist,
+        &ActorState {
+            admin: sender,
+            whitelist: HashMap::from([(sender, true)]),
+        },
+    );
+
+    Ok(())
+}
+
+#[test]
+fn read_altered_state() -> Result<(), anyhow::Error> {
+    let mut env = TestEnv::new()?;
+    let sender = Address::new_id(env.sender());
+    let whitelist = env.deploy_with_params("Whitelist", &ConstructorParams { admin: sender })?;
+
+    let bob = Address::new_id(env.account("bob")?.id);
+    let altered = ActorState {
+        admin: bob,
+        whitelist: HashMap::from([(sender, true)]),
+    };
+    env.alter_state(whitelist, &altered)?;
+
+    let state: ActorState = env.read_state(whitelist)?;
+    assert_eq!(state, altered);
+    env.call(whitelist, "IsWhitelisted", &sender)?
+        .assert_return_eq(true);
+
+    Ok(())
+}
@@ -6,7 +6,11 @@ if [ -n "$1" ]; then
     cp -r templates/actor/* actors/$1
     cp -r templates/test/* "tests/$1-test"
     sed -i "s/<actor-name>/$1/g" actors/$1/Cargo.toml
+    sed -i "s/<actor-name>/$1/g" actors/$1/types/Cargo.toml
+    sed -i "s/<actor_name>/${1//-/_}/g" actors/$1/src/actor.rs
     sed -i "s/<actor-name>/$1/g" "tests/$1-test/Cargo.toml"
+    sed -i "s/<actor-name>/$1/g" "tests/$1-test/build.rs"
+    sed -i "s/<actor_name>/${1//-/_}/g" "tests/$1-test/src/actor.rs"
 else
     echo "Please provide a name for the actor."
     exit 1
@@ -1,41 +1,30 @@
-use cid::{multihash::Code};
-use std::collections::HashMap;
-use frc42_dispatch::{method_hash, match_method};
-use fvm_ipld_blockstore::Block;
-use fvm_ipld_encoding::{RawBytes, DAG_CBOR};
-use fvm_ipld_encoding::ipld_block::IpldBlock;
-use fvm_sdk::NO_DATA_BLOCK_ID;
-use fvm_shared::bigint::Zero;
-use fvm_shared::econ::TokenAmount;
-use fvm_shared::error::ExitCode;
-use fvm_shared::address::Address;
-use fvm_shared::sys::SendFlags;
-use serde_tuple::{Serialize_tuple};
+use actor_utils::error::ActorResult;
+use actor_utils::runtime::Runtime;
+use <actor_name>_types::ActorState;
 
-use crate::utils;
+actor_utils::invoke!(|rt, _input| {
+    use actor_utils::error::dispatch;
+    use frc42_dispatch::match_method;
+    use fvm_sdk::NO_DATA_BLOCK_ID;
+    use fvm_shared::error::ExitCode;
 
-#[no_mangle]
-fn invoke(_input: u32) -> u32 {
-    let method_num = fvm_sdk::message::method_number();
     match_method!(
-        method_num,
+        rt.method_number(),
         {
             "Constructor" => {
-                Constructor();
+                dispatch(Constructor(&rt));
                 NO_DATA_BLOCK_ID
             },
             // TODO add your entry point as match variant
             _ => {
-                fvm_sdk::vm::abort(
-                    ExitCode::USR_UNHANDLED_MESSAGE.value(),
-                    Some("Unknown method number"),
-                );
+                rt.abort(ExitCode::USR_UNHANDLED_MESSAGE, "Unknown method number");
             }
         }
     )
-}
+});
 
 #[allow(non_snake_case)]
-fn Constructor() {
+pub fn Constructor(rt: &impl Runtime) -> ActorResult<()> {
     // TODO add Constructor logic
-}
\ No newline at end of file
+    rt.set_state(&ActorState { placeholder: 0 })
+}
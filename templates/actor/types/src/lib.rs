@@ -0,0 +1,18 @@
+use serde_tuple::{Deserialize_tuple, Serialize_tuple};
+
+/**************************************************
+ * Actor's state
+ **************************************************/
+
+#[derive(Serialize_tuple, Deserialize_tuple, Clone, PartialEq, Debug)]
+pub struct ActorState {
+    // TODO set your actors state properties here
+    pub placeholder: u64
+}
+
+/**************************************************
+ * Method parameters
+ **************************************************/
+
+// TODO declare the parameters and return values of your methods here, as `<Method>Params` and
+// `<Method>Return` to get them in the generated client.
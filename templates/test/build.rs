@@ -0,0 +1,4 @@
+fn main() {
+    actor_codegen::client::build("../../actors/<actor-name>").expect("Could not generate <actor-name> client");
+    actor_codegen::accounts::build("../..").expect("Could not generate accounts");
+}
@@ -1,50 +1,53 @@
-use cid::{multihash::Code};
-use std::collections::HashMap;
-use frc42_dispatch::{method_hash, match_method};
-use fvm_ipld_blockstore::Block;
-use fvm_ipld_encoding::{RawBytes, DAG_CBOR};
-use fvm_ipld_encoding::ipld_block::IpldBlock;
-use fvm_sdk::NO_DATA_BLOCK_ID;
-use fvm_shared::bigint::Zero;
-use fvm_shared::econ::TokenAmount;
-use fvm_shared::error::ExitCode;
-use fvm_shared::address::Address;
-use fvm_shared::sys::SendFlags;
-use serde_tuple::{Serialize_tuple};
+use actor_utils::error::ActorResult;
+use actor_utils::runtime::Runtime;
+use serde_tuple::{Deserialize_tuple, Serialize_tuple};
 
-use crate::utils;
+// Client of the target actor, generated by `build.rs`.
+include!(concat!(env!("OUT_DIR"), "/<actor_name>.rs"));
+
+/**************************************************
+ * Actor's state
+ **************************************************/
+
+#[derive(Serialize_tuple, Deserialize_tuple)]
+pub struct ActorState {
+    // TODO set your actors state properties here
+    pub placeholder: u64
+}
+
+actor_utils::invoke!(|rt, _input| {
+    use actor_utils::error::dispatch;
+    use frc42_dispatch::match_method;
+    use fvm_sdk::NO_DATA_BLOCK_ID;
+    use fvm_shared::error::ExitCode;
 
-#[no_mangle]
-fn invoke(_input: u32) -> u32 {
-    let method_num = fvm_sdk::message::method_number();
     match_method!(
-        method_num,
+        rt.method_number(),
         {
             "Constructor" => {
-                Constructor();
+                dispatch(Constructor(&rt));
                 NO_DATA_BLOCK_ID
             },
             "Setup" => {
-                Setup();
+                dispatch(Setup(&rt));
                 NO_DATA_BLOCK_ID
             },
             // TODO add your entry point as match variant
             _ => {
-                fvm_sdk::vm::abort(
-                    ExitCode::USR_UNHANDLED_MESSAGE.value(),
-                    Some("Unknown method number"),
-                );
+                rt.abort(ExitCode::USR_UNHANDLED_MESSAGE, "Unknown method number");
             }
         }
     )
-}
+});
 
 #[allow(non_snake_case)]
-fn Constructor() {
+pub fn Constructor(rt: &impl Runtime) -> ActorResult<()> {
     // TODO add Constructor logic
+    rt.set_state(&ActorState { placeholder: 0 })
 }
 
 #[allow(non_snake_case)]
-fn Setup() {
+pub fn Setup(_rt: &impl Runtime) -> ActorResult<()> {
     // TODO add Setup logic
-}
\ No newline at end of file
+    Ok(())
+}
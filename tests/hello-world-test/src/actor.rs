@@ -1,3 +1,4 @@
+use actor_utils::assert;
 use cid::{multihash::Code, Cid};
 use frc42_dispatch::match_method;
 use frc42_dispatch::method_hash;
@@ -79,16 +80,7 @@ where
     Ok(fvm_sdk::ipld::put_block(DAG_CBOR, bytes.as_slice())?)
 }
 
-#[no_mangle]
-fn invoke(input: u32) -> u32 {
-    std::panic::set_hook(Box::new(|info| {
-        fvm_sdk::vm::exit(
-            ExitCode::USR_ASSERTION_FAILED.value(),
-            None,
-            Some(&format!("{info}")),
-        )
-    }));
-
+actor_utils::invoke!(|input| {
     let method_num = fvm_sdk::message::method_number();
     match_method!(
         method_num,
@@ -116,7 +108,7 @@ fn invoke(input: u32) -> u32 {
             }
         }
     )
-}
+});
 
 #[allow(non_snake_case)]
 fn Constructor() {
@@ -137,13 +129,7 @@ fn Setup() {
 #[allow(non_snake_case)]
 fn TestConstructorSetup() {
     let state = ActorState::load(&fvm_sdk::sself::root().unwrap());
-    let value = state.value;
-    if state.value != 2u32 {
-        fvm_sdk::vm::abort(
-            ExitCode::USR_ASSERTION_FAILED.value(),
-            Some(&format!("value is different was not called {value}")),
-        )
-    }
+    assert::assert_eq(state.value, 2u32);
 }
 
 // Tests that the target actor Id is properly passed to test methods. At the same time, it also ensures
@@ -162,7 +148,7 @@ fn TestMethodParameter(input: u32) -> u32 {
     )
     .unwrap();
 
-    assert_eq!(res.exit_code, ExitCode::OK);
+    assert::assert_exit_code(&res, ExitCode::OK);
 
     let who_are_you: String = RawBytes::new(
         res.return_data
@@ -172,7 +158,7 @@ fn TestMethodParameter(input: u32) -> u32 {
     .deserialize()
     .unwrap();
 
-    assert_eq!(who_are_you, String::from("Basic Target Actor"));
+    assert::assert_eq(who_are_you, String::from("Basic Target Actor"));
 
     return_ipld(&target_actor_id).unwrap()
 }
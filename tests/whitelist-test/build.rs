@@ -0,0 +1,4 @@
+fn main() {
+    actor_codegen::client::build("../../actors/whitelist").expect("Could not generate whitelist client");
+    actor_codegen::accounts::build("../..").expect("Could not generate accounts");
+}
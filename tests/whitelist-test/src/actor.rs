@@ -1,163 +1,88 @@
-use cid::{multihash::Code};
-use std::collections::HashMap;
-use frc42_dispatch::{method_hash, match_method};
-use fvm_ipld_blockstore::Block;
-use fvm_ipld_encoding::{RawBytes, DAG_CBOR};
-use fvm_ipld_encoding::ipld_block::IpldBlock;
-use fvm_sdk::NO_DATA_BLOCK_ID;
-use fvm_shared::bigint::Zero;
-use fvm_shared::econ::TokenAmount;
-use fvm_shared::error::ExitCode;
+use actor_utils::runtime::Runtime;
+use actor_utils::{assert, cheatcodes};
 use fvm_shared::address::Address;
-use fvm_shared::sys::SendFlags;
-use serde_tuple::{Serialize_tuple};
+use fvm_shared::error::ExitCode;
+use fvm_shared::ActorID;
+
+include!(concat!(env!("OUT_DIR"), "/whitelist.rs"));
+include!(concat!(env!("OUT_DIR"), "/accounts.rs"));
+
+use whitelist::WhitelistClient;
+
+/// Administrator the target is constructed with, see `constructor-params` in Cargo.toml.
+const ADMIN_ID: ActorID = 1234;
 
-use crate::utils;
+actor_utils::invoke!(|rt, input| {
+    use actor_utils::ipld::deserialize_params;
+    use fvm_sdk::NO_DATA_BLOCK_ID;
+    use frc42_dispatch::match_method;
 
-#[no_mangle]
-fn invoke(input: u32) -> u32 {
-    let method_num = fvm_sdk::message::method_number();
     match_method!(
-        method_num,
+        rt.method_number(),
         {
             "TestFailNotAdmin" => {
-                TestFailNotAdmin(input);
+                TestFailNotAdmin(&rt, deserialize_params(input));
+                NO_DATA_BLOCK_ID
+            },
+            "TestNotAdminForbidden" => {
+                TestNotAdminForbidden(&rt, deserialize_params(input));
                 NO_DATA_BLOCK_ID
             },
             "TestHappyPath" => {
-                TestHappyPath(input);
+                TestHappyPath(&rt, deserialize_params(input));
                 NO_DATA_BLOCK_ID
             },
             _ => {
-                fvm_sdk::vm::abort(
-                    ExitCode::USR_UNHANDLED_MESSAGE.value(),
-                    Some("Unknown method number"),
-                );
+                rt.abort(ExitCode::USR_UNHANDLED_MESSAGE, "Unknown method number");
             }
         }
     )
-}
+});
 
 /// Expect the test to fail as we are trying to whitelist while not being the admin
 #[allow(non_snake_case)]
-fn TestFailNotAdmin(input: u32) {
-    let target_actor_id: u64 = utils::deserialize_params(input);
-
-    let res = fvm_sdk::send::send(
-        &Address::new_id(target_actor_id),
-        method_hash!("SetWhitelist"),
-        Some(
-            IpldBlock::serialize(
-                DAG_CBOR,
-                &(Address::new_id(fvm_sdk::message::receiver()), true),
-            )
-                .unwrap(),
-        ),
-        TokenAmount::zero(),
-        None,
-        SendFlags::empty(),
-    )
-        .unwrap();
+pub fn TestFailNotAdmin(rt: &impl Runtime, target_actor_id: ActorID) {
+    let whitelist = WhitelistClient::new(rt, target_actor_id);
 
-    assert_eq!(res.exit_code, ExitCode::OK);
+    whitelist
+        .set_whitelist(Address::new_id(rt.receiver()), true)
+        .expect("Should be able to set whitelist");
 }
 
-/// Test happy path by setting administrator and adding address to whitelist
+/// Test that whitelisting while not being the admin is rejected as forbidden
 #[allow(non_snake_case)]
-fn TestHappyPath(input: u32) {
-    let target_actor_id: u64 = utils::deserialize_params(input);
-
-    set_target_admin(Address::new_id(target_actor_id), Address::new_id(fvm_sdk::message::receiver()));
-
-    let res = fvm_sdk::send::send(
-        &Address::new_id(target_actor_id),
-        method_hash!("SetWhitelist"),
-        Some(
-            IpldBlock::serialize(
-                DAG_CBOR,
-                &(Address::new_id(fvm_sdk::message::receiver()), true),
-            )
-                .unwrap(),
-        ),
-        TokenAmount::zero(),
-        None,
-        SendFlags::empty(),
-    )
-        .unwrap();
-
-    assert_eq!(res.exit_code, ExitCode::OK);
-
-    let res = fvm_sdk::send::send(
-        &Address::new_id(target_actor_id),
-        method_hash!("IsWhitelisted"),
-        Some(
-            IpldBlock::serialize(
-                DAG_CBOR,
-                &Address::new_id(fvm_sdk::message::receiver()),
-            )
-                .unwrap(),
-        ),
-        TokenAmount::zero(),
-        None,
-        SendFlags::empty(),
-    )
-        .unwrap();
-
-    assert_eq!(res.exit_code, ExitCode::OK);
+pub fn TestNotAdminForbidden(rt: &impl Runtime, target_actor_id: ActorID) {
+    let whitelist = WhitelistClient::new(rt, target_actor_id);
+    let bob = accounts::bob();
 
-    let is_whitelisted: bool = RawBytes::new(
-        res.return_data
-            .expect("Should be able to get result from IsWhitelisted of target actor")
-            .data,
-    )
-        .deserialize()
-        .unwrap();
+    bob.prank(rt).expect("Should be able to prank bob");
+    let err = whitelist
+        .set_whitelist(bob.id_address(), true)
+        .expect_err("Should not be able to set whitelist while not being the admin");
 
-    assert!(is_whitelisted);
+    assert::assert_eq(err.exit_code(), Some(ExitCode::USR_FORBIDDEN));
 }
 
+/// Test happy path by adding alice to whitelist as the administrator
+#[allow(non_snake_case)]
+pub fn TestHappyPath(rt: &impl Runtime, target_actor_id: ActorID) {
+    let whitelist = WhitelistClient::new(rt, target_actor_id);
+    let alice = accounts::alice().id_address();
 
-fn set_target_admin(target: Address,  address: Address) {
-    #[derive(Serialize_tuple)]
-    pub struct TargetState {
-        pub(crate) admin: Address,
-        pub(crate) whitelist: HashMap<Address, bool>
-    }
-
-    let new_state = TargetState {
-        admin: address,
-        whitelist: HashMap::new()
-    };
-
-    let serialized = fvm_ipld_encoding::to_vec(&new_state).unwrap();
-    let block = Block {
-        codec: DAG_CBOR,
-        data: serialized,
-    };
-
-    let cid = fvm_sdk::ipld::put(
-        Code::Blake2b256.into(),
-        32,
-        block.codec,
-        block.data.as_ref(),
-    )
-        .unwrap();
-
-    let res = fvm_sdk::send::send(
-        &Address::new_id(98),
-        method_hash!("Alter"),
-        Some(
-            IpldBlock::serialize(
-                DAG_CBOR,
-                &(target, cid.to_string()),
-            )
-                .unwrap(),
-        ),
-        TokenAmount::zero(),
-        None,
-        SendFlags::empty(),
-    )
-        .unwrap();
+    cheatcodes::prank(rt, Address::new_id(ADMIN_ID)).expect("Should be able to prank admin");
+    whitelist
+        .set_whitelist(alice, true)
+        .expect("Should be able to set whitelist");
+
+    let is_whitelisted = whitelist
+        .is_whitelisted(alice)
+        .expect("Should be able to get result from IsWhitelisted of target actor");
+
+    assert::assert_eq(is_whitelisted, true);
 
-    assert_eq!(res.exit_code, ExitCode::OK);
-}
\ No newline at end of file
+    let is_whitelisted = whitelist
+        .is_whitelisted(accounts::bob().id_address())
+        .expect("Should be able to get result from IsWhitelisted of target actor");
+
+    assert::assert_eq(is_whitelisted, false);
+}
@@ -0,0 +1,78 @@
+use actor_utils::assert;
+use actor_utils::runtime::Runtime;
+use actor_utils::targets::Targets;
+use fvm_shared::error::ExitCode;
+
+include!(concat!(env!("OUT_DIR"), "/whitelist.rs"));
+include!(concat!(env!("OUT_DIR"), "/accounts.rs"));
+
+use whitelist::WhitelistClient;
+
+actor_utils::invoke!(|rt, input| {
+    use actor_utils::ipld::deserialize_params;
+    use frc42_dispatch::match_method;
+    use fvm_sdk::NO_DATA_BLOCK_ID;
+
+    match_method!(
+        rt.method_number(),
+        {
+            "TestAdministratorPerWhitelist" => {
+                TestAdministratorPerWhitelist(&rt, deserialize_params(input));
+                NO_DATA_BLOCK_ID
+            },
+            "TestWhitelistsAreIndependent" => {
+                TestWhitelistsAreIndependent(&rt, deserialize_params(input));
+                NO_DATA_BLOCK_ID
+            },
+            _ => {
+                rt.abort(ExitCode::USR_UNHANDLED_MESSAGE, "Unknown method number");
+            }
+        }
+    )
+});
+
+/// Test that alice administers her whitelist only, see `targets` in Cargo.toml
+#[allow(non_snake_case)]
+pub fn TestAdministratorPerWhitelist(rt: &impl Runtime, targets: Targets) {
+    let whitelist = WhitelistClient::new(rt, targets.get("whitelist"));
+    let alice_whitelist = WhitelistClient::new(rt, targets.get("alice_whitelist"));
+    let alice = accounts::alice();
+    let bob = accounts::bob().id_address();
+
+    alice.prank(rt).expect("Should be able to prank alice");
+    alice_whitelist
+        .set_whitelist(bob, true)
+        .expect("Should be able to set whitelist of alice as alice");
+
+    alice.prank(rt).expect("Should be able to prank alice");
+    let err = whitelist
+        .set_whitelist(bob, true)
+        .expect_err("Should not be able to set whitelist of another administrator");
+
+    assert::assert_eq(err.exit_code(), Some(ExitCode::USR_FORBIDDEN));
+}
+
+/// Test that whitelisting bob on one whitelist leaves the other one untouched
+#[allow(non_snake_case)]
+pub fn TestWhitelistsAreIndependent(rt: &impl Runtime, targets: Targets) {
+    let whitelist = WhitelistClient::new(rt, targets.get("whitelist"));
+    let alice_whitelist = WhitelistClient::new(rt, targets.get("alice_whitelist"));
+    let bob = accounts::bob().id_address();
+
+    accounts::alice()
+        .prank(rt)
+        .expect("Should be able to prank alice");
+    alice_whitelist
+        .set_whitelist(bob, true)
+        .expect("Should be able to set whitelist of alice as alice");
+
+    let is_whitelisted = alice_whitelist
+        .is_whitelisted(bob)
+        .expect("Should be able to get result from IsWhitelisted of alice whitelist");
+    assert::assert_eq(is_whitelisted, true);
+
+    let is_whitelisted = whitelist
+        .is_whitelisted(bob)
+        .expect("Should be able to get result from IsWhitelisted of whitelist");
+    assert::assert_eq(is_whitelisted, false);
+}